@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::{Addon, HEMTTError, Project};
+
+static CACHE_DIR: &str = ".hemtt/cache";
+static CACHE_FILE: &str = ".hemtt/cache/build.json";
+
+/// Project settings that affect the contents of every PBO
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Settings {
+    pub prefix: String,
+    pub header_exts: BTreeMap<String, String>,
+    pub exclude: Vec<String>,
+    pub version: String,
+    /// `build` packs config.bin, `pack` packs config.cpp
+    #[serde(default)]
+    pub use_bin: bool,
+}
+impl Settings {
+    pub fn new(p: &Project, use_bin: bool) -> Result<Self, HEMTTError> {
+        Ok(Self {
+            prefix: p.prefix.clone(),
+            header_exts: p.header_exts.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            exclude: p.exclude.clone(),
+            version: p.version()?,
+            use_bin,
+        })
+    }
+}
+
+/// Everything that went into building an addon
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct AddonFingerprint {
+    pub hemtt: String,
+    pub settings: Settings,
+    /// SHA-1 of every file in the addon folder, relative to the folder
+    pub files: BTreeMap<String, String>,
+    /// SHA-1 of every file outside the addon folder that was included while preprocessing
+    #[serde(default = "BTreeMap::new")]
+    pub includes: BTreeMap<String, String>,
+}
+impl AddonFingerprint {
+    pub fn new(addon: &Addon, p: &Project, use_bin: bool) -> Result<Self, HEMTTError> {
+        let folder = addon.folder();
+        let mut files = BTreeMap::new();
+        for entry in walkdir::WalkDir::new(&folder) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.path().is_dir() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .strip_prefix(&folder)
+                .unwrap_or_else(|_| entry.path())
+                .display()
+                .to_string()
+                .replace("\\", "/");
+            files.insert(name, hash_file(entry.path())?);
+        }
+        Ok(Self {
+            hemtt: crate::VERSION.to_string(),
+            settings: Settings::new(p, use_bin)?,
+            files,
+            includes: BTreeMap::new(),
+        })
+    }
+
+    /// Hashes the given included files, skipping those inside the addon folder
+    pub fn hash_includes<'a, I: IntoIterator<Item = &'a Path>>(
+        addon: &Addon,
+        includes: I,
+    ) -> Result<BTreeMap<String, String>, HEMTTError> {
+        let folder = format!("{}/", key(addon));
+        let mut hashes = BTreeMap::new();
+        for include in includes {
+            let name = normalize(include);
+            if name.starts_with(&folder) || hashes.contains_key(&name) {
+                continue;
+            }
+            let hash = if Path::new(&name).exists() {
+                hash_file(Path::new(&name))?
+            } else {
+                String::new()
+            };
+            hashes.insert(name, hash);
+        }
+        Ok(hashes)
+    }
+
+    /// The first of the addon folders, as given by `normalize`, that the addon includes files from
+    pub fn depends_on<'a>(&self, folders: &'a [String]) -> Option<&'a String> {
+        folders
+            .iter()
+            .find(|folder| self.includes.keys().any(|path| path.starts_with(&format!("{}/", folder))))
+    }
+
+    /// The reason a build with this fingerprint differs from a previous one
+    pub fn changes(&self, old: &Self) -> Option<String> {
+        if self.hemtt != old.hemtt {
+            return Some(format!("HEMTT changed from `{}` to `{}`", old.hemtt, self.hemtt));
+        }
+        if self.settings.prefix != old.settings.prefix {
+            return Some("the project prefix changed".to_owned());
+        }
+        if self.settings.header_exts != old.settings.header_exts {
+            return Some("the project header extensions changed".to_owned());
+        }
+        if self.settings.exclude != old.settings.exclude {
+            return Some("the project exclude patterns changed".to_owned());
+        }
+        if self.settings.use_bin != old.settings.use_bin {
+            return Some(if self.settings.use_bin {
+                "the previous PBO was packed without binarizing".to_owned()
+            } else {
+                "the previous PBO was binarized".to_owned()
+            });
+        }
+        if self.settings.version != old.settings.version {
+            return Some(format!(
+                "the version changed from `{}` to `{}`",
+                old.settings.version, self.settings.version
+            ));
+        }
+        for (file, hash) in &self.files {
+            match old.files.get(file) {
+                Some(old_hash) if old_hash == hash => {}
+                Some(_) => return Some(format!("`{}` changed", file)),
+                None => return Some(format!("`{}` was added", file)),
+            }
+        }
+        for file in old.files.keys() {
+            if !self.files.contains_key(file) {
+                return Some(format!("`{}` was removed", file));
+            }
+        }
+        for (file, hash) in &old.includes {
+            match self.includes.get(file) {
+                Some(new_hash) if new_hash == hash => {}
+                Some(new_hash) if new_hash.is_empty() => {
+                    return Some(format!("included file `{}` was removed", file));
+                }
+                _ => return Some(format!("included file `{}` changed", file)),
+            }
+        }
+        None
+    }
+}
+
+/// Persistent record of the inputs of the last successful build of each addon
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct BuildCache {
+    #[serde(default = "BTreeMap::new")]
+    addons: BTreeMap<String, AddonFingerprint>,
+    #[serde(skip)]
+    pending: HashMap<String, AddonFingerprint>,
+}
+impl BuildCache {
+    /// Reads the cache from `.hemtt/cache/`, starting empty if it is missing or unreadable
+    pub fn read() -> Self {
+        if !Path::new(CACHE_FILE).exists() {
+            return Self::default();
+        }
+        let mut data = String::new();
+        if let Err(e) = open_file!(CACHE_FILE).and_then(|mut f| f.read_to_string(&mut data).map_err(From::from)) {
+            debug!("Unable to read the build cache: {}", e);
+            return Self::default();
+        }
+        serde_json::from_str(&data).unwrap_or_else(|e| {
+            debug!("Ignoring invalid build cache: {}", e);
+            Self::default()
+        })
+    }
+
+    pub fn get(&self, addon: &Addon) -> Option<&AddonFingerprint> {
+        self.addons.get(&key(addon))
+    }
+
+    /// The fingerprint staged for the current build, or the one from the last build
+    pub fn latest(&self, addon: &Addon) -> Option<&AddonFingerprint> {
+        self.pending.get(&key(addon)).or_else(|| self.get(addon))
+    }
+
+    /// Holds a fingerprint until the addon has been built
+    pub fn stage(&mut self, addon: &Addon, fingerprint: AddonFingerprint) {
+        self.pending.insert(key(addon), fingerprint);
+    }
+
+    /// Replaces the included files of a staged fingerprint
+    pub fn set_includes(&mut self, addon: &Addon, includes: BTreeMap<String, String>) {
+        if let Some(fingerprint) = self.pending.get_mut(&key(addon)) {
+            fingerprint.includes = includes;
+        }
+    }
+
+    /// Records the staged fingerprint of a successfully built addon
+    pub fn commit(&mut self, addon: &Addon) {
+        if let Some(fingerprint) = self.pending.remove(&key(addon)) {
+            self.addons.insert(key(addon), fingerprint);
+        }
+    }
+
+    pub fn write(&self) -> Result<(), HEMTTError> {
+        create_dir!(CACHE_DIR)?;
+        let out = create_file!(CACHE_FILE)?;
+        serde_json::to_writer_pretty(out, &self)?;
+        Ok(())
+    }
+}
+
+fn key(addon: &Addon) -> String {
+    normalize(&addon.folder())
+}
+
+/// Path relative to the project root using `/` as the separator
+pub fn normalize(path: &Path) -> String {
+    let relative = match std::env::current_dir() {
+        Ok(root) => path.strip_prefix(&root).unwrap_or(path),
+        Err(_) => path,
+    };
+    relative
+        .display()
+        .to_string()
+        .replace("\\", "/")
+        .trim_start_matches("./")
+        .to_string()
+}
+
+/// SHA-1 of a file as a hex string
+pub fn hash_file(path: &Path) -> Result<String, HEMTTError> {
+    let mut buf = Vec::new();
+    open_file!(path)?.read_to_end(&mut buf)?;
+    Ok(format!("{:x}", Sha1::digest(&buf)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint() -> AddonFingerprint {
+        let mut files = BTreeMap::new();
+        files.insert("config.cpp".to_owned(), "a".to_owned());
+        files.insert("script.sqf".to_owned(), "b".to_owned());
+        let mut includes = BTreeMap::new();
+        includes.insert("include/macros.hpp".to_owned(), "c".to_owned());
+        AddonFingerprint {
+            hemtt: "0.7.1".to_owned(),
+            settings: Settings {
+                prefix: "TST".to_owned(),
+                header_exts: BTreeMap::new(),
+                exclude: Vec::new(),
+                version: "1.0.0".to_owned(),
+                use_bin: true,
+            },
+            files,
+            includes,
+        }
+    }
+
+    #[test]
+    fn unchanged() {
+        assert_eq!(None, fingerprint().changes(&fingerprint()));
+    }
+
+    #[test]
+    fn changed_file() {
+        let mut new = fingerprint();
+        new.files.insert("script.sqf".to_owned(), "d".to_owned());
+        assert_eq!(Some("`script.sqf` changed".to_owned()), new.changes(&fingerprint()));
+    }
+
+    #[test]
+    fn added_file() {
+        let mut new = fingerprint();
+        new.files.insert("other.sqf".to_owned(), "d".to_owned());
+        assert_eq!(Some("`other.sqf` was added".to_owned()), new.changes(&fingerprint()));
+    }
+
+    #[test]
+    fn removed_file() {
+        let mut new = fingerprint();
+        new.files.remove("script.sqf");
+        assert_eq!(Some("`script.sqf` was removed".to_owned()), new.changes(&fingerprint()));
+    }
+
+    #[test]
+    fn changed_include() {
+        let mut new = fingerprint();
+        new.includes.insert("include/macros.hpp".to_owned(), "d".to_owned());
+        assert_eq!(
+            Some("included file `include/macros.hpp` changed".to_owned()),
+            new.changes(&fingerprint())
+        );
+        new.includes.insert("include/macros.hpp".to_owned(), String::new());
+        assert_eq!(
+            Some("included file `include/macros.hpp` was removed".to_owned()),
+            new.changes(&fingerprint())
+        );
+    }
+
+    #[test]
+    fn changed_settings() {
+        let mut new = fingerprint();
+        new.settings.version = "1.0.1".to_owned();
+        assert_eq!(
+            Some("the version changed from `1.0.0` to `1.0.1`".to_owned()),
+            new.changes(&fingerprint())
+        );
+
+        let mut new = fingerprint();
+        new.settings.prefix = "ABC".to_owned();
+        assert_eq!(Some("the project prefix changed".to_owned()), new.changes(&fingerprint()));
+
+        let mut new = fingerprint();
+        new.settings.exclude.push("*.psd".to_owned());
+        assert_eq!(
+            Some("the project exclude patterns changed".to_owned()),
+            new.changes(&fingerprint())
+        );
+
+        let mut new = fingerprint();
+        new.hemtt = "0.7.2".to_owned();
+        assert!(new.changes(&fingerprint()).is_some());
+    }
+
+    #[test]
+    fn depends_on() {
+        let mut addon = fingerprint();
+        addon
+            .includes
+            .insert("addons/main/script_macros.hpp".to_owned(), "d".to_owned());
+        let folders = vec!["addons/common".to_owned(), "addons/main".to_owned()];
+        assert_eq!(Some(&folders[1]), addon.depends_on(&folders));
+        assert_eq!(None, addon.depends_on(&folders[..1]));
+        assert_eq!(None, addon.depends_on(&["addons/mai".to_owned()]));
+    }
+
+    #[test]
+    fn changed_build_mode() {
+        let mut packed = fingerprint();
+        packed.settings.use_bin = false;
+        assert_eq!(
+            Some("the previous PBO was packed without binarizing".to_owned()),
+            fingerprint().changes(&packed)
+        );
+        assert_eq!(
+            Some("the previous PBO was binarized".to_owned()),
+            packed.changes(&fingerprint())
+        );
+    }
+}
@@ -29,7 +29,7 @@ impl AddonLocation {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Addon {
     pub name: String,
     pub location: AddonLocation,
@@ -54,12 +54,12 @@ impl Addon {
         target
     }
 
-    pub fn get_variables(&self, p: &Project) -> BTreeMap<&'static str, Json> {
-        let mut vars = p.get_variables();
+    pub fn get_variables(&self, p: &Project) -> Result<BTreeMap<&'static str, Json>, HEMTTError> {
+        let mut vars = p.get_variables()?;
         vars.insert("folder", to_json(self.folder()));
         vars.insert("addon", to_json(self.name.clone()));
         vars.insert("target", to_json(self.target(p).to_str().to_owned()));
-        vars
+        Ok(vars)
     }
 
     /// Folder containing the released addon
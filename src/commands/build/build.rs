@@ -1,28 +1,27 @@
-use std::collections::HashMap;
-use std::io::Cursor;
+use std::io::Write;
 use std::path::PathBuf;
-
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
+use std::sync::Mutex;
 
 use glob::Pattern;
-use linked_hash_map::LinkedHashMap;
 use regex::Regex;
 use walkdir::WalkDir;
 
-use crate::{Addon, HEMTTError, Project, Report, Stage, Task};
+use crate::build::prebuild::preprocess::{can_preprocess, rapify};
+use crate::build::prebuild::render::{can_render, destination};
+use crate::pbo::WritablePBO;
+use crate::{Addon, BuildEvents, FileCache, HEMTTError, Project, Report, Stage, Task};
 
 static BINARIZABLE: &[&str] = &["rtm", "p3d"];
 
 #[derive(Clone)]
 pub struct Build {
     pub use_bin: bool,
+    /// Build each PBO twice and fail if the results differ
+    pub verify_reproducible: bool,
     can_binarize: bool,
 }
 impl Build {
-    pub fn new(use_bin: bool) -> Self {
+    pub fn new(use_bin: bool, verify_reproducible: bool) -> Self {
         let can_binarize = use_bin
             && cfg!(windows)
             && if match armake2::find_binarize_exe() {
@@ -37,22 +36,119 @@ impl Build {
                 warnmessage!("Unable to locate binarize.exe", "Files will be packed as is");
                 false
             };
-        Self { use_bin, can_binarize }
+        Self {
+            use_bin,
+            verify_reproducible,
+            can_binarize,
+        }
     }
 }
 impl Task for Build {
+    fn name(&self) -> &'static str {
+        "build"
+    }
+
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _r: &Report, p: &Project, _: &Stage, pb: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _r: &Report,
+        p: &Project,
+        _: &Stage,
+        events: &dyn BuildEvents,
+        dry_run: bool,
+    ) -> Result<Report, HEMTTError> {
         let mut report = Report::new();
-        let mut pbo = armake2::PBO {
-            files: LinkedHashMap::new(),
-            header_extensions: HashMap::new(),
-            headers: Vec::new(),
-            checksum: None,
-        };
+        let mut data = Vec::new();
+        self.collect(addon, p, events, &mut report, &crate::CACHED)?
+            .write(&mut data)?;
+
+        if self.verify_reproducible {
+            // The second build starts from the sources, not from what the first build cached
+            let fresh = Mutex::new(FileCache::new());
+            self.prepare(addon, p, &fresh)?;
+            let mut again = Vec::new();
+            self.collect(addon, p, events, &mut Report::new(), &fresh)?
+                .write(&mut again)?;
+            if let Some(offset) = data.iter().zip(again.iter()).position(|(a, b)| a != b) {
+                report.stop = Some((
+                    true,
+                    HEMTTError::generic(
+                        "The PBO is not reproducible",
+                        format!("Two builds of `{}` differ at byte {}", addon.name, offset),
+                    ),
+                ));
+                return Ok(report);
+            } else if data.len() != again.len() {
+                report.stop = Some((
+                    true,
+                    HEMTTError::generic(
+                        "The PBO is not reproducible",
+                        format!("Two builds of `{}` differ in size", addon.name),
+                    ),
+                ));
+                return Ok(report);
+            }
+        }
+
+        if dry_run {
+            dryrun!("Would write `{}`", addon.target(p).display());
+            return Ok(report);
+        }
+        let _span = crate::trace::span(addon.target(p).display().to_string(), "file").arg("action", "write");
+        let mut outf = create_file!(addon.target(p))?;
+        outf.write_all(&data)?;
+        Ok(report)
+    }
+}
+
+impl Build {
+    /// Renders and preprocesses the files of the addon again into `cache`
+    fn prepare(&self, addon: &Addon, p: &Project, cache: &Mutex<FileCache>) -> Result<(), HEMTTError> {
+        let vars = addon.get_variables(p)?;
+        for entry in WalkDir::new(&addon.folder()) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if can_render(entry.path()) {
+                let out = crate::render::run(
+                    &std::fs::read_to_string(entry.path())?.replace("\\{", "\\\\{"),
+                    entry.path().to_str(),
+                    &vars,
+                )?;
+                lock!(cache, "cache").insert(&destination(entry.path()), out)?;
+            }
+        }
+        if !self.use_bin {
+            return Ok(());
+        }
+        for entry in WalkDir::new(&addon.folder()) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if !can_preprocess(entry.path()) {
+                continue;
+            }
+            let (original_path, rendered_path) = lock!(RENDERED).get_paths(entry.path().display().to_string());
+            let raw = lock!(cache, "cache").clean_comments(&rendered_path)?;
+            if raw.len() < 3 {
+                continue;
+            }
+            let out = rapify(raw, &original_path, p, cache, |_| {})?;
+            lock!(cache, "cache").insert_bytes(&rendered_path.replace("config.cpp", "config.bin"), out)?;
+        }
+        Ok(())
+    }
+
+    /// Reads every file of the addon into a PBO, the files are read through `cache`
+    fn collect(
+        &self,
+        addon: &Addon,
+        p: &Project,
+        events: &dyn BuildEvents,
+        report: &mut Report,
+        cache: &Mutex<FileCache>,
+    ) -> Result<WritablePBO, HEMTTError> {
+        let mut pbo = WritablePBO::new();
         let directory = addon.folder();
         let binarize =
             self.can_binarize && !(directory.join("$NOBIN$").exists() || directory.join("$NOBIN-NOTEST$").exists());
@@ -64,13 +160,14 @@ impl Task for Build {
             if entry.path().is_dir() {
                 continue;
             }
-            if crate::build::prebuild::render::can_render(entry.path()) {
+            if can_render(entry.path()) {
                 continue;
             }
             if exclude_patterns.iter().any(|x| x.matches(entry.path().to_str().unwrap())) {
                 continue;
             }
-            pb.set_message(&entry.path().display().to_string());
+            events.file_processed(addon, "Pack", &entry.path().display().to_string());
+            let _span = crate::trace::span(entry.path().display().to_string(), "file").arg("action", "pack");
             let name = entry
                 .path()
                 .display()
@@ -87,11 +184,7 @@ impl Task for Build {
             let is_binarizable = binarize && BINARIZABLE.contains(&ext.as_str());
 
             if name == "$PBOPREFIX$" {
-                let content = crate::CACHED
-                    .lock()
-                    .unwrap()
-                    .lines(&entry.path().display().to_string())?
-                    .clone();
+                let content = lock!(cache, "cache").lines(&entry.path().display().to_string())?.clone();
                 for line in content {
                     if line.is_empty() {
                         break;
@@ -99,44 +192,26 @@ impl Task for Build {
 
                     let eq: Vec<String> = line.split('=').map(|s| s.to_string()).collect();
                     if eq.len() == 1 {
-                        pbo.header_extensions.insert("prefix".to_string(), line.to_string());
+                        pbo.add_extension("prefix", line.to_string());
                     } else {
-                        pbo.header_extensions.insert(eq[0].clone(), eq[1].clone());
+                        pbo.add_extension(eq[0].clone(), eq[1].clone());
                     }
                 }
             } else {
-                let content = crate::CACHED
-                    .lock()
-                    .unwrap()
-                    .read(&entry.path().display().to_string())?
-                    .clone();
+                let content = lock!(cache, "cache").read(&entry.path().display().to_string())?.clone();
                 if crate::build::prebuild::preprocess::RAPABLE.contains(&ext.as_ref()) {
                     if self.use_bin {
-                        pbo.files.insert(
+                        pbo.add_file(
                             name.replace("config.cpp", "config.bin"),
-                            Cursor::new(
-                                crate::CACHED
-                                    .lock()
-                                    .unwrap()
-                                    .read(&entry.path().display().to_string().replace("config.cpp", "config.bin"))?
-                                    .into_boxed_slice(),
-                            ),
+                            lock!(cache, "cache")
+                                .read(&entry.path().display().to_string().replace("config.cpp", "config.bin"))?,
                         );
                     } else {
-                        pbo.files.insert(
-                            name,
-                            Cursor::new(
-                                crate::CACHED
-                                    .lock()
-                                    .unwrap()
-                                    .read(&entry.path().display().to_string())?
-                                    .into_boxed_slice(),
-                            ),
-                        );
+                        pbo.add_file(name, content);
                     }
                 } else if cfg!(windows) && is_binarizable {
                     let cursor = armake2::binarize(&PathBuf::from(entry.path()))?;
-                    pbo.files.insert(name, cursor);
+                    pbo.add_file(name, cursor.into_inner().into_vec());
                 } else {
                     if is_binarizable && !cfg!(windows) {
                         report.warnings.push(HEMTTError::generic(
@@ -145,28 +220,23 @@ impl Task for Build {
                         ));
                     }
 
-                    pbo.files.insert(
-                        Regex::new(".p3do$").unwrap().replace_all(&name, ".p3d").to_string(),
-                        Cursor::new(content.into_boxed_slice()),
-                    );
+                    pbo.add_file(Regex::new(".p3do$").unwrap().replace_all(&name, ".p3d"), content);
                 }
             }
         }
 
         // Add projects header extensions
         for header_ext in &p.header_exts {
-            pbo.header_extensions.insert(
+            pbo.add_extension(
                 header_ext.0.to_string(),
                 crate::render::run(
                     header_ext.1,
                     Some(&format!("project:header_ext:{}", header_ext.0)),
-                    &addon.get_variables(p),
+                    &addon.get_variables(p)?,
                 )?,
             );
         }
 
-        let mut outf = create_file!(addon.target(p))?;
-        pbo.write(&mut outf)?;
-        Ok(report)
+        Ok(pbo)
     }
 }
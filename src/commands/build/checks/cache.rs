@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use crate::cache::AddonFingerprint;
+use crate::{Addon, BuildEvents, HEMTTError, Project, Report, Stage, Task};
+
+// Skips addons whose inputs have not changed since the last build
+#[derive(Clone)]
+pub struct Cache {
+    /// Must match the `use_bin` of the build that follows
+    pub use_bin: bool,
+}
+impl Task for Cache {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
+        Ok(true)
+    }
+
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        p: &Project,
+        _: &Stage,
+        _: &dyn BuildEvents,
+        _: bool,
+    ) -> Result<Report, HEMTTError> {
+        let mut report = Report::new();
+        let previous = crate::BUILD_CACHE.lock().unwrap().get(addon).cloned();
+        let mut fingerprint = AddonFingerprint::new(addon, p, self.use_bin)?;
+        if let Some(old) = &previous {
+            // Check the files included by the previous build, they are replaced if the addon is preprocessed again
+            fingerprint.includes = AddonFingerprint::hash_includes(addon, old.includes.keys().map(Path::new))?;
+        }
+        let target = addon.target(p);
+        let reason = if !target.exists() {
+            Some("the PBO does not exist".to_owned())
+        } else {
+            match &previous {
+                Some(old) => fingerprint.changes(old),
+                None => Some("no previous build was recorded".to_owned()),
+            }
+        };
+        if let Some(reason) = reason {
+            debug!("Rebuilding `{}`: {}", addon.name, reason);
+            report.rebuild = Some(reason);
+        } else {
+            report.stop = Some((
+                false,
+                HEMTTError::GENERIC("The PBO is up to date".to_owned(), target.display().to_string()),
+            ));
+        }
+        crate::BUILD_CACHE.lock().unwrap().stage(addon, fingerprint);
+        Ok(report)
+    }
+}
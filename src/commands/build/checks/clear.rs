@@ -1,27 +1,38 @@
 use std::path::PathBuf;
 
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
-
 use regex::Regex;
 use strum::IntoEnumIterator;
 
-use crate::{Addon, AddonList, AddonLocation, HEMTTError, Project, Report, Stage, Task};
+use crate::{Addon, AddonList, AddonLocation, BuildEvents, HEMTTError, Project, Report, Stage, Task};
 
 // Clears existing files that will be rebuilt only
 #[derive(Clone)]
 pub struct Clear {}
 impl Task for Clear {
+    fn name(&self) -> &'static str {
+        "clear"
+    }
+
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _: &Report, p: &Project, _: &Stage, _pb: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        p: &Project,
+        _: &Stage,
+        _: &dyn BuildEvents,
+        dry_run: bool,
+    ) -> Result<Report, HEMTTError> {
         let target = addon.target(p);
         if target.exists() {
-            remove_file!(target)?;
+            if dry_run {
+                dryrun!("Would delete `{}`", target.display());
+            } else {
+                remove_file!(target)?;
+            }
         }
         Ok(Report::new())
     }
@@ -31,15 +42,17 @@ impl Task for Clear {
 #[derive(Clone)]
 pub struct Clean {}
 impl Task for Clean {
-    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage) -> AddonList {
+    fn name(&self) -> &'static str {
+        "clean"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
         let re = Regex::new(r"(?m)(.+?)\.pbo$").unwrap();
-        let mut targets = Vec::new();
-        for data in &addons {
-            if let Ok(d) = data {
-                let (_, addon) = d;
-                targets.push(addon.target(p).display().to_string());
-            }
-        }
+        // Use every addon, not only those selected, so PBOs of unselected addons are kept
+        let targets: Vec<String> = crate::build::get_all_addons()?
+            .iter()
+            .map(|addon| addon.target(p).display().to_string())
+            .collect();
         for dir in AddonLocation::iter() {
             let dir = dir.to_string();
             if !PathBuf::from(&dir).exists() {
@@ -50,7 +63,11 @@ impl Task for Clean {
                 let path = entry.path();
                 let loc = path.display().to_string();
                 if !path.is_dir() && re.is_match(&loc) && !targets.contains(&loc) {
-                    remove_file!(&loc)?;
+                    if dry_run {
+                        dryrun!("Would delete `{}`", loc);
+                    } else {
+                        remove_file!(&loc)?;
+                    }
                 }
             }
         }
@@ -1,3 +1,3 @@
+pub mod cache;
 pub mod clear;
-pub mod modtime;
 pub mod names;
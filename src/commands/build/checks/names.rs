@@ -1,20 +1,27 @@
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
-
 use regex::Regex;
 
-use crate::{Addon, AddonLocation, HEMTTError, Project, Report, Stage, Task};
+use crate::{Addon, AddonLocation, BuildEvents, HEMTTError, Project, Report, Stage, Task};
 
 #[derive(Clone)]
 pub struct NotEmpty {}
 impl Task for NotEmpty {
+    fn name(&self) -> &'static str {
+        "not_empty"
+    }
+
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _: &Report, _: &Project, _: &Stage, _: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        _: &Project,
+        _: &Stage,
+        _: &dyn BuildEvents,
+        _: bool,
+    ) -> Result<Report, HEMTTError> {
         let mut report = Report::new();
         let empty = std::fs::read_dir(addon.folder())?.count() == 0;
         if empty {
@@ -30,11 +37,23 @@ impl Task for NotEmpty {
 #[derive(Clone)]
 pub struct ValidName {}
 impl Task for ValidName {
+    fn name(&self) -> &'static str {
+        "valid_name"
+    }
+
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _: &Report, p: &Project, _: &Stage, _: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        p: &Project,
+        _: &Stage,
+        _: &dyn BuildEvents,
+        _: bool,
+    ) -> Result<Report, HEMTTError> {
         let mut report = Report::new();
         // WARN: addon name standards
         let re = Regex::new(r"^([A-z0-9\-]+)$").unwrap();
@@ -1,5 +1,7 @@
 use std::path::Path;
 
+use glob::Pattern;
+
 pub mod addon;
 #[allow(clippy::module_inception)]
 pub mod build;
@@ -12,14 +14,15 @@ use crate::{Addon, AddonLocation, Command, Flow, HEMTTError, Project, Stage, Ste
 pub struct Build {}
 impl Command for Build {
     fn register(&self) -> clap::App {
-        clap::SubCommand::with_name("build")
+        let sub = clap::SubCommand::with_name("build")
             .version(*crate::VERSION)
             .about("Build the Project")
             .arg(
                 clap::Arg::with_name("release")
                     .help("Build a release")
                     .long("release")
-                    .conflicts_with("dev"),
+                    .conflicts_with("dev")
+                    .conflicts_with_all(&["addon", "exclude-addon", "location"]),
             )
             .arg(
                 clap::Arg::with_name("rebuild")
@@ -33,16 +36,34 @@ impl Command for Build {
                     .help("Remove an existing release")
                     .long("force-release"),
             )
+            .arg(
+                clap::Arg::with_name("no-verify")
+                    .help("Do not verify the signatures of a release")
+                    .long("no-verify")
+                    .requires("release"),
+            )
+            .arg(
+                clap::Arg::with_name("keep-going")
+                    .help("Continue building other addons when an addon fails")
+                    .long("keep-going"),
+            )
+            .arg(
+                clap::Arg::with_name("dry-run")
+                    .help("Show what would be done without changing any files")
+                    .long("dry-run"),
+            )
+            .arg(
+                clap::Arg::with_name("verify-reproducible")
+                    .help("Build each PBO twice and fail if the results differ")
+                    .long("verify-reproducible"),
+            )
+            .arg(crate::output::report_arg());
+        crate::build::addon_args(sub)
     }
 
     fn run(&self, args: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
-        let mut addons = crate::build::get_addons(AddonLocation::Addons)?;
-        if Path::new(&AddonLocation::Optionals.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Optionals)?);
-        }
-        if Path::new(&AddonLocation::Compats.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Compats)?);
-        }
+        let addons = crate::build::get_selected_addons(args)?;
+        let reports = crate::output::targets(args)?;
         let flow = Flow {
             steps: vec![
                 Step::single(
@@ -69,7 +90,7 @@ impl Command for Build {
                         Box::new(crate::build::prebuild::render::Render {}),
                         Box::new(crate::build::checks::names::NotEmpty {}),
                         Box::new(crate::build::checks::names::ValidName {}),
-                        Box::new(crate::build::checks::modtime::ModTime {}),
+                        Box::new(crate::build::checks::cache::Cache { use_bin: true }),
                     ],
                 ),
                 Step::single("📜", "", Stage::Check, vec![Box::new(crate::flow::Script {})]),
@@ -84,7 +105,16 @@ impl Command for Build {
                     "📝",
                     "Build",
                     Stage::Build,
-                    vec![Box::new(crate::build::build::Build::new(true))],
+                    vec![Box::new(crate::build::build::Build::new(
+                        true,
+                        args.is_present("verify-reproducible"),
+                    ))],
+                ),
+                Step::single(
+                    "💾",
+                    "",
+                    Stage::Build,
+                    vec![Box::new(crate::build::postbuild::cache::SaveCache {})],
                 ),
                 Step::single("📜", "", Stage::PostBuild, vec![Box::new(crate::flow::Script {})]),
                 if args.is_present("release") {
@@ -99,6 +129,16 @@ impl Command for Build {
                 } else {
                     Step::none()
                 },
+                if args.is_present("release") && p.changelog.is_some() {
+                    Step::single(
+                        "⭐",
+                        "Changelog",
+                        Stage::ReleaseBuild,
+                        vec![Box::new(crate::build::postbuild::changelog::Changelog {})],
+                    )
+                } else {
+                    Step::none()
+                },
                 if args.is_present("release") {
                     Step::single(
                         "⭐",
@@ -109,16 +149,132 @@ impl Command for Build {
                 } else {
                     Step::none()
                 },
+                if args.is_present("release") {
+                    Step::single(
+                        "⭐",
+                        "Manifest",
+                        Stage::ReleaseBuild,
+                        vec![Box::new(crate::build::postbuild::manifest::WriteManifest {})],
+                    )
+                } else {
+                    Step::none()
+                },
                 if args.is_present("release") {
                     Step::single("📜", "", Stage::ReleaseBuild, vec![Box::new(crate::flow::Script {})])
                 } else {
                     Step::none()
                 },
+                // Last, so the release scripts cannot change the release after it was verified
+                if args.is_present("release") && !args.is_present("no-verify") {
+                    Step::single(
+                        "⭐",
+                        "Verify",
+                        Stage::ReleaseBuild,
+                        vec![Box::new(crate::build::postbuild::verify::Verify {})],
+                    )
+                } else {
+                    Step::none()
+                },
             ],
+            keep_going: args.is_present("keep-going"),
+            dry_run: args.is_present("dry-run"),
+            time: args.is_present("time"),
+            events: crate::flow::default_events(args.is_present("dry-run")),
         };
-        flow.execute(addons, &mut p)?;
-        Ok(())
+        let (addons, result) = flow.execute(addons, &mut p);
+        // Written before the error is returned, the report of a failed build is often the one worth looking at
+        crate::output::write(&reports, &addons, &p)?;
+        result?;
+        flow.summary(&addons)
+    }
+}
+
+/// Arguments used to select which addons a command uses
+pub fn addon_args<'a, 'b>(sub: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
+    sub.arg(
+        clap::Arg::with_name("addon")
+            .help("Only use addons matching the name, supports glob patterns")
+            .long("addon")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+    )
+    .arg(
+        clap::Arg::with_name("exclude-addon")
+            .help("Skip addons matching the name, supports glob patterns")
+            .long("exclude-addon")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+    )
+    .arg(
+        clap::Arg::with_name("location")
+            .help("Only use addons from the location")
+            .long("location")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .possible_values(&["addons", "optionals", "compats"]),
+    )
+}
+
+/// Addons matching the arguments from `addon_args`
+pub fn get_selected_addons(args: &clap::ArgMatches) -> Result<Vec<Addon>, HEMTTError> {
+    select_addons(
+        get_all_addons()?,
+        args.values_of("location").map(Iterator::collect),
+        args.values_of("addon").map(Iterator::collect),
+        args.values_of("exclude-addon").map(Iterator::collect),
+    )
+}
+
+fn select_addons(
+    all: Vec<Addon>,
+    locations: Option<Vec<&str>>,
+    names: Option<Vec<&str>>,
+    excludes: Option<Vec<&str>>,
+) -> Result<Vec<Addon>, HEMTTError> {
+    let mut addons = all.clone();
+    if let Some(locations) = locations {
+        addons.retain(|addon| locations.contains(&addon.location.to_string().as_str()));
+    }
+    if let Some(names) = names {
+        let patterns = get_patterns(names.into_iter(), &addons)?;
+        addons.retain(|addon| patterns.iter().any(|pattern| pattern.matches(&addon.name)));
+    }
+    if let Some(excludes) = excludes {
+        // Excluding an addon that another argument already dropped is not an error
+        let patterns = get_patterns(excludes.into_iter(), &all)?;
+        addons.retain(|addon| !patterns.iter().any(|pattern| pattern.matches(&addon.name)));
+    }
+    Ok(addons)
+}
+
+fn get_patterns<'a, I: Iterator<Item = &'a str>>(names: I, addons: &[Addon]) -> Result<Vec<Pattern>, HEMTTError> {
+    let mut patterns = Vec::new();
+    for name in names {
+        let pattern = Pattern::new(name)?;
+        if !addons.iter().any(|addon| pattern.matches(&addon.name)) {
+            return Err(HEMTTError::generic(
+                format!("No addon matches `{}`", name),
+                "Addons are matched by their folder name",
+            ));
+        }
+        patterns.push(pattern);
+    }
+    Ok(patterns)
+}
+
+/// All addons in `addons/`, `optionals/` and `compats/`
+pub fn get_all_addons() -> Result<Vec<Addon>, HEMTTError> {
+    let mut addons = get_addons(AddonLocation::Addons)?;
+    if Path::new(&AddonLocation::Optionals.to_string()).exists() {
+        addons.extend(get_addons(AddonLocation::Optionals)?);
     }
+    if Path::new(&AddonLocation::Compats.to_string()).exists() {
+        addons.extend(get_addons(AddonLocation::Compats)?);
+    }
+    Ok(addons)
 }
 
 pub fn get_addons(location: AddonLocation) -> Result<Vec<Addon>, HEMTTError> {
@@ -131,3 +287,82 @@ pub fn get_addons(location: AddonLocation) -> Result<Vec<Addon>, HEMTTError> {
         })
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addons() -> Vec<Addon> {
+        vec![
+            Addon {
+                name: "main".to_owned(),
+                location: AddonLocation::Addons,
+            },
+            Addon {
+                name: "common".to_owned(),
+                location: AddonLocation::Addons,
+            },
+            Addon {
+                name: "tracers".to_owned(),
+                location: AddonLocation::Optionals,
+            },
+            Addon {
+                name: "ace".to_owned(),
+                location: AddonLocation::Compats,
+            },
+        ]
+    }
+
+    fn names(addons: Vec<Addon>) -> Vec<String> {
+        addons.into_iter().map(|addon| addon.name).collect()
+    }
+
+    #[test]
+    fn patterns() {
+        assert_eq!(2, get_patterns(vec!["main", "t*"].into_iter(), &addons()).unwrap().len());
+        assert!(get_patterns(vec!["missing"].into_iter(), &addons()).is_err());
+        assert!(get_patterns(vec!["["].into_iter(), &addons()).is_err());
+    }
+
+    #[test]
+    fn select_all() {
+        assert_eq!(
+            vec!["main", "common", "tracers", "ace"],
+            names(select_addons(addons(), None, None, None).unwrap())
+        );
+    }
+
+    #[test]
+    fn select_location() {
+        assert_eq!(
+            vec!["main", "common"],
+            names(select_addons(addons(), Some(vec!["addons"]), None, None).unwrap())
+        );
+    }
+
+    #[test]
+    fn select_names() {
+        assert_eq!(
+            vec!["common", "ace"],
+            names(select_addons(addons(), None, Some(vec!["c*", "ace"]), None).unwrap())
+        );
+        assert!(select_addons(addons(), Some(vec!["addons"]), Some(vec!["tracers"]), None).is_err());
+    }
+
+    #[test]
+    fn select_excludes() {
+        assert_eq!(
+            vec!["main", "tracers", "ace"],
+            names(select_addons(addons(), None, None, Some(vec!["common"])).unwrap())
+        );
+        assert_eq!(
+            vec!["main", "common"],
+            names(select_addons(addons(), Some(vec!["addons"]), None, Some(vec!["tracers"])).unwrap())
+        );
+        assert_eq!(
+            vec!["main"],
+            names(select_addons(addons(), None, Some(vec!["main"]), Some(vec!["common"])).unwrap())
+        );
+        assert!(select_addons(addons(), None, None, Some(vec!["missing"])).is_err());
+    }
+}
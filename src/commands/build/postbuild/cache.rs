@@ -0,0 +1,25 @@
+use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
+
+// Records the inputs of every addon that was built
+#[derive(Clone)]
+pub struct SaveCache {}
+impl Task for SaveCache {
+    fn name(&self) -> &'static str {
+        "save_cache"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, _: &Project, _: &Stage, dry_run: bool) -> AddonList {
+        let mut cache = crate::BUILD_CACHE.lock().unwrap();
+        for data in &addons {
+            if let Ok((report, addon)) = data {
+                if report.stop.is_none() {
+                    cache.commit(addon);
+                }
+            }
+        }
+        if !dry_run {
+            cache.write()?;
+        }
+        Ok(addons)
+    }
+}
@@ -0,0 +1,28 @@
+use std::io::Write;
+
+use crate::commands::changelog::changelog;
+use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
+
+/// Writes the changes since the previous tag to the release
+#[derive(Clone)]
+pub struct Changelog {}
+impl Task for Changelog {
+    fn name(&self) -> &'static str {
+        "changelog"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
+        let group = match p.changelog {
+            Some(group) => group,
+            None => return Ok(addons),
+        };
+        let target = p.release_dir()?.join("CHANGELOG.md");
+        let text = changelog(None, "HEAD", group, &p.version()?)?;
+        if dry_run {
+            dryrun!("Would write `{}`", target.display());
+            return Ok(addons);
+        }
+        create_file!(target)?.write_all(text.as_bytes())?;
+        Ok(addons)
+    }
+}
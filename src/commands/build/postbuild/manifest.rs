@@ -0,0 +1,189 @@
+use std::io::Read;
+use std::path::Path;
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
+
+/// Name of the manifest inside the release folder
+pub static MANIFEST: &str = "hemtt-manifest.json";
+
+#[derive(Deserialize, Serialize)]
+pub struct Manifest {
+    pub version: String,
+    /// Commit the release was built from, if the project is a git repository
+    pub commit: Option<String>,
+    pub hemtt: String,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ManifestFile {
+    /// Path relative to the release folder using `/` as the separator
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Lists every file of the release with its checksum
+#[derive(Clone)]
+pub struct WriteManifest {}
+impl Task for WriteManifest {
+    fn name(&self) -> &'static str {
+        "manifest"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
+        let release_folder = p.release_dir()?;
+        if dry_run {
+            dryrun!("Would write `{}`", release_folder.join(MANIFEST).display());
+            return Ok(addons);
+        }
+        let manifest = Manifest {
+            version: p.version()?,
+            commit: git2::Repository::open(".")
+                .and_then(|repo| repo.revparse_single("HEAD"))
+                .map(|rev| rev.id().to_string())
+                .ok(),
+            hemtt: crate::VERSION.to_string(),
+            files: files(&release_folder)?,
+        };
+        let out = create_file!(release_folder.join(MANIFEST))?;
+        serde_json::to_writer_pretty(out, &manifest)?;
+        Ok(addons)
+    }
+}
+
+/// Every file of the release except the manifest
+fn files(release: &Path) -> Result<Vec<ManifestFile>, HEMTTError> {
+    let mut files = Vec::new();
+    for path in paths(release)? {
+        let (size, sha256) = hash(&release.join(&path))?;
+        files.push(ManifestFile { path, size, sha256 });
+    }
+    Ok(files)
+}
+
+/// Paths of the files in the release relative to it, except the manifest
+fn paths(release: &Path) -> Result<Vec<String>, HEMTTError> {
+    let mut paths = Vec::new();
+    for entry in WalkDir::new(release).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if !entry.path().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .strip_prefix(release)
+            .unwrap_or_else(|_| entry.path())
+            .display()
+            .to_string()
+            .replace("\\", "/");
+        if path != MANIFEST {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Size and SHA-256 of a file
+fn hash(path: &Path) -> Result<(u64, String), HEMTTError> {
+    let mut buf = Vec::new();
+    open_file!(path)?.read_to_end(&mut buf)?;
+    Ok((buf.len() as u64, format!("{:x}", Sha256::digest(&buf))))
+}
+
+/// Checks the files of a release against its manifest
+pub fn check(release: &Path) -> Result<(), HEMTTError> {
+    let manifest: Manifest = serde_json::from_reader(open_file!(release.join(MANIFEST))?)?;
+    let mut problems = 0;
+    for file in &manifest.files {
+        let path = release.join(&file.path);
+        if !path.exists() {
+            error!("`{}` is missing", file.path);
+            problems += 1;
+            continue;
+        }
+        let (size, sha256) = hash(&path)?;
+        if size != file.size || sha256 != file.sha256 {
+            error!("`{}` does not match the manifest", file.path);
+            problems += 1;
+        }
+    }
+    for path in paths(release)? {
+        if !manifest.files.iter().any(|f| f.path == path) {
+            error!("`{}` is not in the manifest", path);
+            problems += 1;
+        }
+    }
+
+    if problems > 0 {
+        return Err(HEMTTError::simple(format!("Found {} manifest problem(s)", problems)));
+    }
+    println!(
+        "{} {} files of version {}",
+        "Verified".green().bold(),
+        manifest.files.len(),
+        manifest.version
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TempDir;
+
+    /// A release folder in the temporary folder with a manifest of its files, `name` keeps the tests apart
+    fn release(name: &str) -> TempDir {
+        let release = TempDir::new(&format!("manifest-{}", name));
+        std::fs::create_dir_all(release.join("addons")).unwrap();
+        std::fs::write(release.join("mod.cpp"), "name = \"Test\";").unwrap();
+        std::fs::write(release.join("addons").join("tst_main.pbo"), b"pbo").unwrap();
+        let manifest = Manifest {
+            version: "1.0.0".to_owned(),
+            commit: None,
+            hemtt: "test".to_owned(),
+            files: files(&release).unwrap(),
+        };
+        serde_json::to_writer(std::fs::File::create(release.join(MANIFEST)).unwrap(), &manifest).unwrap();
+        release
+    }
+
+    #[test]
+    fn listed() {
+        let release = release("listed");
+        let paths: Vec<String> = files(&release).unwrap().into_iter().map(|file| file.path).collect();
+        assert_eq!(vec!["addons/tst_main.pbo", "mod.cpp"], paths);
+    }
+
+    #[test]
+    fn check_matching() {
+        let release = release("matching");
+        assert!(check(&release).is_ok());
+    }
+
+    #[test]
+    fn check_changed() {
+        let release = release("changed");
+        std::fs::write(release.join("addons").join("tst_main.pbo"), b"obp").unwrap();
+        assert!(check(&release).is_err());
+    }
+
+    #[test]
+    fn check_missing() {
+        let release = release("missing");
+        std::fs::remove_file(release.join("mod.cpp")).unwrap();
+        assert!(check(&release).is_err());
+    }
+
+    #[test]
+    fn check_added() {
+        let release = release("added");
+        std::fs::write(release.join("meta.cpp"), "name = \"Test\";").unwrap();
+        assert!(check(&release).is_err());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod cache;
+pub mod changelog;
+pub mod manifest;
 pub mod release;
 pub mod sign;
+pub mod verify;
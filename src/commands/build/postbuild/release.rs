@@ -1,6 +1,10 @@
+use std::io::Write;
+use std::path::Path;
+
 use dialoguer::Confirmation;
 use glob::glob;
 
+use crate::project::ModCpp;
 use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
 
 #[derive(Clone)]
@@ -8,7 +12,17 @@ pub struct Release {
     pub force_release: bool,
 }
 impl Task for Release {
-    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage) -> AddonList {
+    fn name(&self) -> &'static str {
+        "release"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
+        let release_folder = p.release_dir()?;
+        if dry_run {
+            list_changes(&addons, &release_folder, self.force_release, p)?;
+            return Ok(addons);
+        }
+
         let addons: Vec<_> = addons
             .into_iter()
             .map(|d| {
@@ -18,7 +32,6 @@ impl Task for Release {
             .collect();
 
         // Prepare release directory
-        let release_folder = p.release_dir()?;
         if release_folder.exists() {
             if self.force_release {
                 std::fs::remove_dir_all(&release_folder)?;
@@ -67,6 +80,76 @@ impl Task for Release {
             addon.release(&release_folder, &p)?;
         }
 
+        if let Some(modcpp) = &p.modcpp {
+            write_modcpp(modcpp, &release_folder, p)?;
+        }
+
         Ok(addons)
     }
 }
+
+/// Generates `mod.cpp` and `meta.cpp` from the `[modcpp]` section
+fn write_modcpp(modcpp: &ModCpp, release_folder: &Path, p: &Project) -> Result<(), HEMTTError> {
+    for image in modcpp.images(p)? {
+        if !release_folder.join(&image).exists() {
+            return Err(HEMTTError::generic(
+                format!("`mod.cpp` references `{}` which is not in the release", image),
+                "Add the image to `files` in the project file",
+            ));
+        }
+    }
+    create_file!(release_folder.join("mod.cpp"))?.write_all(modcpp.mod_cpp(p)?.as_bytes())?;
+    if let Some(meta) = modcpp.meta_cpp(p)? {
+        create_file!(release_folder.join("meta.cpp"))?.write_all(meta.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Lists what a release would remove, create and copy
+fn list_changes(
+    addons: &[Result<(Report, Addon), HEMTTError>],
+    release_folder: &Path,
+    force_release: bool,
+    p: &Project,
+) -> Result<(), HEMTTError> {
+    if release_folder.exists() {
+        if force_release {
+            dryrun!("Would delete the existing release `{}`", release_folder.display());
+        } else if *crate::CI {
+            return Err(HEMTTError::generic(
+                "Release already exists",
+                "Use `--force-release` to overwrite",
+            ));
+        } else {
+            dryrun!("Would ask to delete the existing release `{}`", release_folder.display());
+        }
+    }
+    dryrun!("Would create `{}`", release_folder.join("keys").display());
+    for file in &p.files {
+        for entry in glob(file)? {
+            if let Ok(path) = entry {
+                dryrun!(
+                    "Would copy `{}` to `{}`",
+                    path.display(),
+                    release_folder.join(path.file_name().unwrap()).display()
+                );
+            }
+        }
+    }
+    for data in addons {
+        if let Ok((_, addon)) = data {
+            dryrun!(
+                "Would copy `{}` to `{}`",
+                addon.target(p).display(),
+                addon.release_target(&release_folder.to_path_buf(), p).display()
+            );
+        }
+    }
+    if let Some(modcpp) = &p.modcpp {
+        dryrun!("Would generate `{}`", release_folder.join("mod.cpp").display());
+        if modcpp.workshop_id.is_some() {
+            dryrun!("Would generate `{}`", release_folder.join("meta.cpp").display());
+        }
+    }
+    Ok(())
+}
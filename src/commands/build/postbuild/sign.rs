@@ -1,30 +1,48 @@
-use std::path::Path;
-
 use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
 use armake2::{BIPrivateKey, PBO};
 
 #[derive(Clone)]
 pub struct Sign {}
 impl Task for Sign {
-    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage) -> AddonList {
-        create_dir!("keys/")?;
+    fn name(&self) -> &'static str {
+        "sign"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
         let key_name = p.get_key_name()?;
-        let key = if p.reuse_private_key() {
+        if dry_run {
+            list_changes(&addons, &key_name, p)?;
+            return Ok(addons);
+        }
+        create_dir!("keys/")?;
+        let private = p.get_private_key_path()?;
+        let key = if !p.private_key_env.is_empty() {
+            crate::commands::keys::read_env_private_key(&p.private_key_env)?
+        } else if p.external_private_key() {
+            if !private.exists() {
+                return Err(HEMTTError::generic(
+                    format!("Unable to find the private key `{}`", private.display()),
+                    "Use `hemtt keys generate` to create it",
+                ));
+            }
+            crate::commands::keys::read_private_key(&private)?
+        } else if p.reuse_private_key() {
             warn!("`Reuse Private Key` is enabled. This should be disabled unless you know what you are doing.");
-            if Path::new(&format!("keys/{}.biprivatekey", key_name)).exists() {
-                BIPrivateKey::read(&mut open_file!(format!("keys/{}.biprivatekey", key_name))?)
-                    .expect("Failed to read private key")
+            if private.exists() {
+                crate::commands::keys::read_private_key(&private)?
             } else {
                 // Generate and write the keypair to disk in the current directory
-                let privatekey = BIPrivateKey::generate(1024, key_name.clone());
-                privatekey.write(&mut create_file!(format!("keys/{}.biprivatekey", key_name))?)?;
+                let privatekey = BIPrivateKey::generate(p.get_key_size()?, key_name.clone());
+                privatekey.write(&mut create_file!(private)?)?;
                 privatekey
             }
         } else {
-            BIPrivateKey::generate(1024, key_name.clone())
+            BIPrivateKey::generate(p.get_key_size()?, key_name.clone())
         };
 
         let release_folder = p.release_dir()?;
+        // A provided key keeps its own name
+        let key_name = key.name.clone();
 
         // Generate a public key to match the private key
         key.to_public_key()
@@ -50,3 +68,38 @@ impl Task for Sign {
         Ok(addons)
     }
 }
+
+/// Lists the keys and signatures a release would create
+fn list_changes(addons: &[Result<(Report, Addon), HEMTTError>], key_name: &str, p: &Project) -> Result<(), HEMTTError> {
+    let release_folder = p.release_dir()?;
+    let private = p.get_private_key_path()?;
+    if !p.private_key_env.is_empty() {
+        crate::commands::keys::read_env_private_key(&p.private_key_env)?;
+        dryrun!("Would use the private key in `{}`", p.private_key_env);
+    } else if (p.external_private_key() || p.reuse_private_key()) && private.exists() {
+        dryrun!("Would use the private key `{}`", private.display());
+    } else if p.external_private_key() {
+        return Err(HEMTTError::generic(
+            format!("Unable to find the private key `{}`", private.display()),
+            "Use `hemtt keys generate` to create it",
+        ));
+    } else if p.reuse_private_key() {
+        dryrun!("Would generate the private key `{}`", private.display());
+    } else {
+        dryrun!("Would generate a private key for `{}`", key_name);
+    }
+    dryrun!("Would write `keys/{}.bikey`", key_name);
+    dryrun!(
+        "Would copy `keys/{}.bikey` to `{}`",
+        key_name,
+        release_folder.join("keys").join(format!("{}.bikey", key_name)).display()
+    );
+    for d in addons {
+        if let Ok((_, addon)) = d {
+            let mut location = addon.release_location(&release_folder);
+            location.push(p.get_sig_name(&addon.name)?);
+            dryrun!("Would sign `{}` as `{}`", addon.name, location.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use std::path::Path;
+
+use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
+
+/// Checks the signatures of the release
+#[derive(Clone)]
+pub struct Verify {}
+impl Task for Verify {
+    fn name(&self) -> &'static str {
+        "verify"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, _: &Stage, dry_run: bool) -> AddonList {
+        let release_folder = p.release_dir()?;
+        if dry_run {
+            dryrun!("Would verify the signatures in `{}` against `keys`", release_folder.display());
+            return Ok(addons);
+        }
+        crate::commands::verify::verify(&release_folder, Path::new("keys"))?;
+        Ok(addons)
+    }
+}
@@ -1,18 +1,14 @@
 use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
+use std::sync::Mutex;
 
 use regex::Regex;
 use walkdir::WalkDir;
 
-use crate::{Addon, FileErrorLineNumber, HEMTTError, Project, Report, Stage, Task};
+use crate::cache::AddonFingerprint;
+use crate::{Addon, BuildEvents, FileCache, FileErrorLineNumber, HEMTTError, Project, Report, Stage, Task};
 
 pub static RAPABLE: &[&str] = &["cpp", "rvmat", "ext"];
-static CMD_GAP: usize = 18;
 
 pub fn can_preprocess(p: &Path) -> bool {
     RAPABLE.contains(&p.extension().unwrap_or_else(|| std::ffi::OsStr::new("")).to_str().unwrap())
@@ -21,95 +17,141 @@ pub fn can_preprocess(p: &Path) -> bool {
 #[derive(Clone)]
 pub struct Preprocess {}
 impl Task for Preprocess {
+    fn name(&self) -> &'static str {
+        "preprocess"
+    }
+
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _: &Report, p: &Project, _: &Stage, pb: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        p: &Project,
+        _: &Stage,
+        events: &dyn BuildEvents,
+        dry_run: bool,
+    ) -> Result<Report, HEMTTError> {
         let mut report = Report::new();
+        // Every file resolved while preprocessing, used to rebuild when a shared header changes
+        let resolved: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
         for entry in WalkDir::new(&addon.folder()) {
-            pb.set_message("Looking for files to preprocess");
-            pb.tick();
             let path = entry.unwrap();
             let can_rap = can_preprocess(&path.path());
             if can_rap {
-                pb.set_message("Waiting for render lock");
-                let (original_path, rendered_path) =
-                    crate::RENDERED.lock().unwrap().get_paths(path.path().display().to_string());
-                pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Reading"), rendered_path));
-                let raw = crate::CACHED.lock().unwrap().clean_comments(&rendered_path)?.clone();
+                let _span = crate::trace::span(path.path().display().to_string(), "file").arg("action", "preprocess");
+                let (original_path, rendered_path) = lock!(RENDERED).get_paths(path.path().display().to_string());
+                events.file_processed(addon, "Reading", &rendered_path);
+                let raw = lock!(CACHED).clean_comments(&rendered_path)?.clone();
                 if raw.len() < 3 {
-                    pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Skipping"), rendered_path));
+                    events.file_processed(addon, "Skipping", &rendered_path);
                     continue;
                 }
-                let mut includes = p.include.clone();
-                includes.insert(0, PathBuf::from("."));
-                pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Preprocess"), rendered_path));
+                events.file_processed(addon, "Preprocess", &rendered_path);
                 // match preprocess(raw.clone(), Some(PathBuf::from(&original_path)), &includes, |path| {
                 //     pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Preprocess"), rendered_path));
                 //     crate::CACHED.lock().unwrap().clean_comments(path.to_str().unwrap())
                 // }) {
-                match armake2::Config::from_string(raw.clone(), Some(PathBuf::from(&original_path)), &includes, |path| {
-                    pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Preprocess"), rendered_path));
-                    crate::CACHED.lock().unwrap().clean_comments(path.to_str().unwrap()).unwrap()
-                }) {
-                    Ok(rapped) => {
-                        pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Rapify"), rendered_path));
-                        // let mut warnings: Vec<(usize, String, Option<&'static str>)> = Vec::new();
-                        // let rapped = armake2::Config::from_string(&output, Some(PathBuf::from(&original_path)))
-                        //     .map_err(|e| HEMTTError::from_armake_parse(e, &rendered_path, Some(output.clone())))?;
-                        // let total = warnings.len();
-                        // for (i, w) in warnings.into_iter().enumerate() {
-                        //     let text = format!("Report {}/{}", i, total);
-                        //     pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, &text), rendered_path));
-                        //     let mut line = output[..w.0].chars().filter(|c| c == &'\n').count();
-                        //     let file = info.line_origins[min(line, info.line_origins.len()) - 1]
-                        //         .1
-                        //         .as_ref()
-                        //         .map(|p| p.to_str().unwrap().to_string());
-                        //     line = info.line_origins[min(line, info.line_origins.len()) - 1].0 as usize + 1;
-
-                        //     let filename = file.unwrap();
-                        //     report.warnings.push(HEMTTError::LINENO(FileErrorLineNumber {
-                        //         content: crate::CACHED.lock().unwrap().get_line(&filename, line)?,
-                        //         col: None,
-                        //         line: Some(line),
-                        //         file: filename,
-                        //         error: w.1,
-                        //         note: None,
-                        //     }));
-                        // }
-                        pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, "Caching"), rendered_path));
-                        let mut c = Cursor::new(Vec::new());
-                        rapped.write_rapified(&mut c)?;
-                        c.seek(SeekFrom::Start(0))?;
-                        let mut out = Vec::new();
-                        c.read_to_end(&mut out)?;
-                        pb.set_message("Waiting for cache lock");
-                        crate::CACHED
-                            .lock()
-                            .unwrap()
-                            .insert_bytes(&rendered_path.replace("config.cpp", "config.bin"), out)?;
+                let rapified = rapify(raw, &original_path, p, &crate::CACHED, |path| {
+                    events.file_processed(addon, "Preprocess", &rendered_path);
+                    resolved.lock().unwrap().push(path.to_path_buf());
+                });
+                match rapified {
+                    Ok(out) => {
+                        events.file_processed(addon, "Caching", &rendered_path);
+                        if dry_run {
+                            dryrun!("Would rapify `{}`", rendered_path);
+                        }
+                        lock!(CACHED).insert_bytes(&rendered_path.replace("config.cpp", "config.bin"), out)?;
                     }
                     Err(e) => {
                         // Unable to clone HEMTTError
                         //report.unique_error(HEMTTError::from(e));
-                        report.stop = Some((true, HEMTTError::from(e)));
+                        report.stop = Some((true, e));
                     }
                 }
             }
         }
+        let resolved = resolved.into_inner().unwrap();
+        let hashes = AddonFingerprint::hash_includes(addon, resolved.iter().map(PathBuf::as_path))?;
+        crate::BUILD_CACHE.lock().unwrap().set_includes(addon, hashes);
         Ok(report)
     }
 }
 
+/// Preprocesses and rapifies a file, the file and its includes are read through `cache`
+///
+/// `included` is called with every file that is included
+pub fn rapify<F: Fn(&Path)>(
+    raw: String,
+    original_path: &str,
+    p: &Project,
+    cache: &Mutex<FileCache>,
+    included: F,
+) -> Result<Vec<u8>, HEMTTError> {
+    let mut includes = p.include.clone();
+    includes.insert(0, PathBuf::from("."));
+    // The callback can't return an error, the first one is kept and returned after preprocessing
+    let failed: Mutex<Option<HEMTTError>> = Mutex::new(None);
+    let rapped = armake2::Config::from_string(raw, Some(PathBuf::from(original_path)), &includes, |path| {
+        included(path);
+        let content = match path.to_str() {
+            Some(path) => lock!(cache, "cache").clean_comments(path).map_err(HEMTTError::from),
+            None => Err(HEMTTError::generic(
+                format!("Unable to read `{}`", path.display()),
+                "The path is not valid UTF-8",
+            )),
+        };
+        content.unwrap_or_else(|e| {
+            failed.lock().unwrap().get_or_insert(e);
+            String::new()
+        })
+    });
+    if let Some(e) = failed.into_inner().unwrap() {
+        return Err(e);
+    }
+    let rapped = rapped?;
+    // let mut warnings: Vec<(usize, String, Option<&'static str>)> = Vec::new();
+    // let rapped = armake2::Config::from_string(&output, Some(PathBuf::from(&original_path)))
+    //     .map_err(|e| HEMTTError::from_armake_parse(e, &rendered_path, Some(output.clone())))?;
+    // let total = warnings.len();
+    // for (i, w) in warnings.into_iter().enumerate() {
+    //     let text = format!("Report {}/{}", i, total);
+    //     pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, &text), rendered_path));
+    //     let mut line = output[..w.0].chars().filter(|c| c == &'\n').count();
+    //     let file = info.line_origins[min(line, info.line_origins.len()) - 1]
+    //         .1
+    //         .as_ref()
+    //         .map(|p| p.to_str().unwrap().to_string());
+    //     line = info.line_origins[min(line, info.line_origins.len()) - 1].0 as usize + 1;
+
+    //     let filename = file.unwrap();
+    //     report.warnings.push(HEMTTError::LINENO(FileErrorLineNumber {
+    //         content: crate::CACHED.lock().unwrap().get_line(&filename, line)?,
+    //         col: None,
+    //         line: Some(line),
+    //         file: filename,
+    //         error: w.1,
+    //         note: None,
+    //     }));
+    // }
+    let mut c = Cursor::new(Vec::new());
+    rapped.write_rapified(&mut c)?;
+    c.seek(SeekFrom::Start(0))?;
+    let mut out = Vec::new();
+    c.read_to_end(&mut out)?;
+    Ok(out)
+}
+
 pub fn convert_preprocess_error(error: String) -> Result<HEMTTError, HEMTTError> {
     let include_error = Regex::new(r#"(?m)File "(.+?)" included from "(.+?)" not found."#).unwrap();
     let unexpected_token =
         Regex::new(r#"(?ms)(?:.+?)In line (.+?):(\d+?):(.+?)Unexpected token "(.+?)", expected: (.+?)$"#).unwrap();
     if include_error.is_match(&error) {
         let cap = include_error.captures(&error).unwrap();
-        let contents = crate::CACHED.lock().unwrap().lines(&cap[2])?;
+        let contents = lock!(CACHED).lines(&cap[2])?;
         for (i, content) in contents.into_iter().enumerate() {
             if content.contains(&format!("#include \"{}\"", &cap[1])) {
                 return Ok(HEMTTError::LINENO(FileErrorLineNumber {
@@ -131,10 +173,8 @@ pub fn convert_preprocess_error(error: String) -> Result<HEMTTError, HEMTTError>
             line: Some(line),
             file: cap[1].to_string(),
             note: None,
-            content: crate::CACHED.lock().unwrap().get_line(&cap[1], line)?,
+            content: lock!(CACHED).get_line(&cap[1], line)?,
         }));
     }
-    std::fs::write("armake2.error", &error)?;
-    eprintln!("unknown armake error `{}`", error);
-    unimplemented!()
+    Ok(HEMTTError::generic("Unable to preprocess", error))
 }
@@ -1,22 +1,26 @@
 use std::io::Write;
 use std::path::Path;
 
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
-
 use walkdir::WalkDir;
 
-use crate::{Addon, HEMTTError, Project, Report, Stage, Task};
+use crate::{Addon, BuildEvents, HEMTTError, Project, Report, Stage, Task};
 
 pub fn can_render(p: &Path) -> bool {
     let name = p.file_name().unwrap_or_else(|| std::ffi::OsStr::new("")).to_str().unwrap();
     name.contains(".ht.") || name.ends_with(".ht")
 }
 
-pub fn render(path: &Path, addon: &Addon, p: &Project) -> Result<Report, HEMTTError> {
-    let vars = &addon.get_variables(p);
+/// Path a file is rendered to, `.ht` is removed from the name
+pub fn destination(path: &Path) -> String {
+    path.display()
+        .to_string()
+        .replace(".ht.", ".")
+        .trim_end_matches(".ht")
+        .to_string()
+}
+
+pub fn render(path: &Path, addon: &Addon, p: &Project, dry_run: bool) -> Result<Report, HEMTTError> {
+    let vars = &addon.get_variables(p)?;
     let mut report = Report::new();
     match crate::render::run(
         &std::fs::read_to_string(path)?.replace("\\{", "\\\\{"),
@@ -24,27 +28,20 @@ pub fn render(path: &Path, addon: &Addon, p: &Project) -> Result<Report, HEMTTEr
         vars,
     ) {
         Ok(out) => {
-            let dest = path
-                .display()
-                .to_string()
-                .replace(".ht.", ".")
-                .trim_end_matches(".ht")
-                .to_string();
-            let mut outfile = create_file!(Path::new(&dest))?;
-            outfile.write_all(out.as_bytes())?;
-            debug!("Rendered `{}` to `{}`", path.display(), dest);
-            crate::RENDERED
-                .lock()
-                .unwrap()
-                .add(path.display().to_string(), dest.clone())?;
-            crate::CACHED.lock().unwrap().insert(&dest, out)?;
+            let dest = destination(path);
+            if dry_run {
+                dryrun!("Would render `{}` to `{}`", path.display(), dest);
+            } else {
+                let mut outfile = create_file!(Path::new(&dest))?;
+                outfile.write_all(out.as_bytes())?;
+                debug!("Rendered `{}` to `{}`", path.display(), dest);
+            }
+            lock!(RENDERED).add(path.display().to_string(), dest.clone())?;
+            lock!(CACHED).insert(&dest, out)?;
         }
         Err(err) => {
             if let HEMTTError::LINENO(mut e) = err {
-                e.content = crate::CACHED
-                    .lock()
-                    .unwrap()
-                    .get_line(path.as_os_str().to_str().unwrap(), e.line.unwrap_or(1))?;
+                e.content = lock!(CACHED).get_line(path.as_os_str().to_str().unwrap(), e.line.unwrap_or(1))?;
                 e.file = path.display().to_string();
                 report.unique_error(HEMTTError::LINENO(e));
             } else {
@@ -58,17 +55,30 @@ pub fn render(path: &Path, addon: &Addon, p: &Project) -> Result<Report, HEMTTEr
 #[derive(Clone)]
 pub struct Render {}
 impl Task for Render {
+    fn name(&self) -> &'static str {
+        "render"
+    }
+
     fn can_run(&self, _addon: &Addon, _: &Report, _p: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(true)
     }
 
-    fn parallel(&self, addon: &Addon, _: &Report, p: &Project, _: &Stage, pb: &ProgressBar) -> Result<Report, HEMTTError> {
+    fn parallel(
+        &self,
+        addon: &Addon,
+        _: &Report,
+        p: &Project,
+        _: &Stage,
+        events: &dyn BuildEvents,
+        dry_run: bool,
+    ) -> Result<Report, HEMTTError> {
         let mut report = Report::new();
         for entry in WalkDir::new(&addon.folder()) {
             let path = entry.unwrap();
             if can_render(&path.path()) {
-                pb.set_message(&format!("Render: {}", path.path().display().to_string()));
-                report.absorb(render(path.path(), addon, p)?);
+                events.file_processed(addon, "Render", &path.path().display().to_string());
+                let _span = crate::trace::span(path.path().display().to_string(), "file").arg("action", "render");
+                report.absorb(render(path.path(), addon, p, dry_run)?);
             }
         }
         Ok(report)
@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::Path;
+
+use git2::{Commit, Oid, Repository, Sort};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{Command, HEMTTError, Project};
+
+/// Conventional commit types and their headings, in the order they are listed
+static TYPES: &[(&str, &str)] = &[
+    ("feat", "Features"),
+    ("fix", "Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+];
+static OTHER: &str = "Other";
+
+lazy_static::lazy_static! {
+    static ref CONVENTIONAL: Regex = Regex::new(r"^(\w+)(?:\([^)]*\))?!?: (.+)$").unwrap();
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Group {
+    /// By conventional commit type
+    Type,
+    /// By the addon folders a commit touched
+    Addon,
+}
+impl Group {
+    pub fn parse(group: &str) -> Result<Self, HEMTTError> {
+        match group {
+            "type" => Ok(Group::Type),
+            "addon" => Ok(Group::Addon),
+            _ => Err(HEMTTError::generic(
+                format!("Invalid changelog group `{}`", group),
+                "Use `type` or `addon`",
+            )),
+        }
+    }
+}
+
+pub struct Changelog {}
+impl Command for Changelog {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("changelog")
+            .version(*crate::VERSION)
+            .about("Generate a changelog from the git history")
+            .arg(
+                clap::Arg::with_name("from")
+                    .help("Tag or commit to start after, defaults to the previous tag")
+                    .long("from")
+                    .takes_value(true),
+            )
+            .arg(
+                clap::Arg::with_name("to")
+                    .help("Tag or commit to end at")
+                    .long("to")
+                    .takes_value(true)
+                    .default_value("HEAD"),
+            )
+            .arg(
+                clap::Arg::with_name("group")
+                    .help("Group the commits by conventional commit type or by addon")
+                    .long("group")
+                    .takes_value(true)
+                    .possible_values(&["type", "addon"])
+                    .default_value("type"),
+            )
+            .arg(
+                clap::Arg::with_name("output")
+                    .help("File to write, prints the changelog if not set")
+                    .long("output")
+                    .short("o")
+                    .takes_value(true),
+            )
+    }
+
+    fn run(&self, args: &clap::ArgMatches, p: Project) -> Result<(), HEMTTError> {
+        let to = args.value_of("to").unwrap();
+        let title = if to == "HEAD" { p.version()? } else { to.to_string() };
+        let text = changelog(
+            args.value_of("from"),
+            to,
+            Group::parse(args.value_of("group").unwrap())?,
+            &title,
+        )?;
+        match args.value_of("output") {
+            Some(output) => create_file!(output)?.write_all(text.as_bytes())?,
+            None => print!("{}", text),
+        }
+        Ok(())
+    }
+}
+
+/// Markdown list of the commits after `from` up to `to`, `from` defaults to the previous tag
+pub fn changelog(from: Option<&str>, to: &str, group: Group, title: &str) -> Result<String, HEMTTError> {
+    let repo = Repository::open(".")?;
+    let to = repo.revparse_single(to)?.peel_to_commit()?.id();
+    let from = match from {
+        Some(from) => Some(repo.revparse_single(from)?.peel_to_commit()?.id()),
+        None => previous_tag(&repo, to)?,
+    };
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL);
+    walk.push(to)?;
+    if let Some(from) = from {
+        walk.hide(from)?;
+    }
+
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let (kind, description) = conventional(commit.summary().unwrap_or_default());
+        let line = format!("- {} ({})", description, &commit.id().to_string()[..8]);
+        let headings = match group {
+            Group::Type => vec![heading(kind.as_ref().map(String::as_str))],
+            Group::Addon => addons(&repo, &commit)?,
+        };
+        for heading in headings {
+            groups.entry(heading).or_default().push(line.clone());
+        }
+    }
+
+    Ok(markdown(title, &groups))
+}
+
+/// The type and description of a conventional commit summary, other summaries have no type
+fn conventional(summary: &str) -> (Option<String>, String) {
+    match CONVENTIONAL.captures(summary) {
+        Some(c) => (Some(c[1].to_lowercase()), c[2].to_string()),
+        None => (None, summary.to_string()),
+    }
+}
+
+/// Lists the lines of each heading, the headings are ordered by `rank`
+fn markdown(title: &str, groups: &BTreeMap<String, Vec<String>>) -> String {
+    let mut headings: Vec<&String> = groups.keys().collect();
+    headings.sort_by_key(|h| rank(h));
+    let mut out = format!("# {}\n", title);
+    for heading in headings {
+        out.push_str(&format!("\n## {}\n\n", heading));
+        for line in &groups[heading] {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// The closest tagged commit before `to`
+fn previous_tag(repo: &Repository, to: Oid) -> Result<Option<Oid>, HEMTTError> {
+    let mut tags: HashMap<Oid, String> = HashMap::new();
+    for name in repo.tag_names(None)?.iter().filter_map(|n| n) {
+        let commit = repo.revparse_single(&format!("refs/tags/{}", name))?.peel_to_commit()?;
+        tags.insert(commit.id(), name.to_string());
+    }
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL);
+    walk.push(to)?;
+    for oid in walk {
+        let oid = oid?;
+        if oid != to && tags.contains_key(&oid) {
+            debug!("Changelog starts after `{}`", tags[&oid]);
+            return Ok(Some(oid));
+        }
+    }
+    Ok(None)
+}
+
+fn heading(kind: Option<&str>) -> String {
+    TYPES
+        .iter()
+        .find(|(t, _)| Some(*t) == kind)
+        .map(|(_, heading)| heading.to_string())
+        .unwrap_or_else(|| OTHER.to_string())
+}
+
+/// Type headings in their listed order, then addons by name, then everything else
+fn rank(heading: &str) -> (usize, String) {
+    match TYPES.iter().position(|(_, h)| *h == heading) {
+        Some(i) => (i, String::new()),
+        None if heading == OTHER => (TYPES.len() + 1, String::new()),
+        None => (TYPES.len(), heading.to_string()),
+    }
+}
+
+/// Addons with files changed by the commit
+fn addons(repo: &Repository, commit: &Commit) -> Result<Vec<String>, HEMTTError> {
+    let parent = if commit.parent_count() > 0 {
+        Some(commit.parent(0)?.tree()?)
+    } else {
+        None
+    };
+    let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+    let mut addons = Vec::new();
+    for delta in diff.deltas() {
+        if let Some(name) = delta.new_file().path().or_else(|| delta.old_file().path()).and_then(addon) {
+            if !addons.contains(&name) {
+                addons.push(name);
+            }
+        }
+    }
+    if addons.is_empty() {
+        addons.push(OTHER.to_string());
+    }
+    Ok(addons)
+}
+
+/// Name of the addon a file belongs to, for files inside `addons/`, `optionals/` or `compats/`
+fn addon(path: &Path) -> Option<String> {
+    let mut parts = path.iter().map(|p| p.to_string_lossy().to_string());
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(folder), Some(name), Some(_)) if ["addons", "optionals", "compats"].contains(&folder.as_str()) => Some(name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group() {
+        assert_eq!(Group::Addon, serde_json::from_str("\"addon\"").unwrap());
+        assert_eq!(Group::Type, serde_json::from_str("\"type\"").unwrap());
+        assert!(serde_json::from_str::<Group>("\"folder\"").is_err());
+        assert!(Group::parse("folder").is_err());
+    }
+
+    #[test]
+    fn conventional_commits() {
+        assert_eq!(
+            (Some("feat".to_owned()), "Add a thing".to_owned()),
+            conventional("feat: Add a thing")
+        );
+        assert_eq!(
+            (Some("fix".to_owned()), "Fix a thing".to_owned()),
+            conventional("Fix(main)!: Fix a thing")
+        );
+        assert_eq!((None, "Fix a thing".to_owned()), conventional("Fix a thing"));
+        assert_eq!("Features", heading(Some("feat")));
+        assert_eq!(OTHER, heading(Some("chore")));
+        assert_eq!(OTHER, heading(None));
+    }
+
+    #[test]
+    fn addon_folders() {
+        assert_eq!(Some("main".to_owned()), addon(Path::new("addons/main/config.cpp")));
+        assert_eq!(Some("tracers".to_owned()), addon(Path::new("optionals/tracers/config.cpp")));
+        assert_eq!(Some("ace".to_owned()), addon(Path::new("compats/ace/config.cpp")));
+        assert_eq!(None, addon(Path::new("addons/README.md")));
+        assert_eq!(None, addon(Path::new("tools/build/script.py")));
+    }
+
+    #[test]
+    fn ranking() {
+        let mut groups = BTreeMap::new();
+        for heading in &[OTHER, "main", "Fixes", "common", "Features"] {
+            groups.insert(heading.to_string(), vec![format!("- {}", heading)]);
+        }
+        let headings: Vec<String> = markdown("1.0.0", &groups)
+            .lines()
+            .filter(|line| line.starts_with("## "))
+            .map(|line| line[3..].to_string())
+            .collect();
+        assert_eq!(vec!["Features", "Fixes", "common", "main", OTHER], headings);
+    }
+}
@@ -1,23 +1,22 @@
-use std::path::Path;
-
-use crate::{AddonLocation, Command, Flow, HEMTTError, Project, Stage, Step};
+use crate::{Command, Flow, HEMTTError, Project, Stage, Step};
 
 pub struct Clean {}
 impl Command for Clean {
     fn register(&self) -> clap::App {
-        clap::SubCommand::with_name("clean")
-            .version(*crate::VERSION)
-            .about("Clean built files")
+        crate::build::addon_args(
+            clap::SubCommand::with_name("clean")
+                .version(*crate::VERSION)
+                .about("Clean built files")
+                .arg(
+                    clap::Arg::with_name("dry-run")
+                        .help("Show what would be removed without removing it")
+                        .long("dry-run"),
+                ),
+        )
     }
 
-    fn run(&self, _: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
-        let mut addons = crate::build::get_addons(AddonLocation::Addons)?;
-        if Path::new(&AddonLocation::Optionals.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Optionals)?);
-        }
-        if Path::new(&AddonLocation::Compats.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Compats)?);
-        }
+    fn run(&self, args: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
+        let addons = crate::build::get_selected_addons(args)?;
         let flow = Flow {
             steps: vec![
                 Step::single(
@@ -33,8 +32,13 @@ impl Command for Clean {
                     vec![Box::new(crate::build::checks::clear::Clear {})],
                 ),
             ],
+            keep_going: false,
+            dry_run: args.is_present("dry-run"),
+            time: args.is_present("time"),
+            events: crate::flow::default_events(args.is_present("dry-run")),
         };
-        flow.execute(addons, &mut p)?;
-        Ok(())
+        let (addons, result) = flow.execute(addons, &mut p);
+        result?;
+        flow.summary(&addons)
     }
 }
@@ -0,0 +1,335 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Cursor;
+use std::path::Path;
+
+use armake2::config::{ConfigArray, ConfigArrayElement, ConfigEntry};
+use colored::*;
+use walkdir::WalkDir;
+
+use crate::pbo::{decompress, PBOEntry, ReadablePBO, COMPRESSED};
+use crate::{Command, HEMTTError};
+
+/// Properties of every class, keyed by the path of the class
+type Classes = BTreeMap<String, BTreeMap<String, String>>;
+
+pub struct Diff {}
+impl Command for Diff {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("diff")
+            .version(*crate::VERSION)
+            .about("Compare two PBOs or two release folders")
+            .arg(
+                clap::Arg::with_name("a")
+                    .help("Original PBO or release folder")
+                    .required(true),
+            )
+            .arg(clap::Arg::with_name("b").help("Changed PBO or release folder").required(true))
+    }
+
+    fn require_project(&self) -> bool {
+        false
+    }
+
+    fn run_no_project(&self, args: &clap::ArgMatches) -> Result<(), HEMTTError> {
+        let a = Path::new(args.value_of("a").unwrap());
+        let b = Path::new(args.value_of("b").unwrap());
+        let changes = if a.is_dir() && b.is_dir() {
+            diff_folders(a, b)?
+        } else if a.is_file() && b.is_file() {
+            diff_pbo(a, b, &a.file_name().unwrap().to_string_lossy())?
+        } else {
+            return Err(HEMTTError::generic(
+                "Unable to compare the paths",
+                "Both paths must be PBOs or both must be folders",
+            ));
+        };
+        if changes == 0 {
+            println!("No differences");
+            return Ok(());
+        }
+        // Like `diff`, differences end with a non-zero exit code so scripts can check for them
+        Err(HEMTTError::simple(format!("Found {} difference(s)", changes)))
+    }
+}
+
+/// Compares every file in two release folders, returning the number of differences
+fn diff_folders(a: &Path, b: &Path) -> Result<usize, HEMTTError> {
+    let files_a = files(a)?;
+    let files_b = files(b)?;
+    let mut changes = 0;
+    for file in files_a.difference(&files_b) {
+        println!("{} {}", "-".red().bold(), file);
+        changes += 1;
+    }
+    for file in files_b.difference(&files_a) {
+        println!("{} {}", "+".green().bold(), file);
+        changes += 1;
+    }
+    for file in files_a.intersection(&files_b) {
+        let (path_a, path_b) = (a.join(file), b.join(file));
+        if file.ends_with(".pbo") {
+            changes += diff_pbo(&path_a, &path_b, file)?;
+        } else if std::fs::read(&path_a)? != std::fs::read(&path_b)? {
+            println!("{} {}", "~".yellow().bold(), file);
+            changes += 1;
+        }
+    }
+    Ok(changes)
+}
+
+/// Files in a folder relative to it, using `/`
+fn files(root: &Path) -> Result<BTreeSet<String>, HEMTTError> {
+    let mut files = BTreeSet::new();
+    for entry in WalkDir::new(root) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        if entry.path().is_file() {
+            let relative = entry.path().strip_prefix(root).unwrap();
+            files.insert(relative.display().to_string().replace('\\', "/"));
+        }
+    }
+    Ok(files)
+}
+
+/// Compares the header extensions and entries of two PBOs, returning the number of differences
+///
+/// A changed `config.bin` counts every class that changed instead of the file
+fn diff_pbo(a: &Path, b: &Path, name: &str) -> Result<usize, HEMTTError> {
+    let pbo_a = ReadablePBO::read(&mut open_file!(a)?)?;
+    let pbo_b = ReadablePBO::read(&mut open_file!(b)?)?;
+    let mut lines = Vec::new();
+
+    let extensions_a: BTreeMap<&str, &str> = pbo_a.extensions.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let extensions_b: BTreeMap<&str, &str> = pbo_b.extensions.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let mut changes = diff_maps(&extensions_a, &extensions_b, "header ", &mut lines);
+
+    let entries_a = entries(&pbo_a)?;
+    let entries_b = entries(&pbo_b)?;
+    for (entry, data) in &entries_a {
+        match entries_b.get(entry) {
+            None => {
+                lines.push(format!("{} {}", "-".red().bold(), entry));
+                changes += 1;
+            }
+            Some(other) if other != data => {
+                lines.push(format!("{} {}", "~".yellow().bold(), entry));
+                let classes = if entry.ends_with("config.bin") {
+                    let classes_a = classes(&derapify(data)?);
+                    let classes_b = classes(&derapify(other)?);
+                    diff_classes(&classes_a, &classes_b, &mut lines)
+                } else {
+                    0
+                };
+                changes += classes.max(1);
+            }
+            _ => {}
+        }
+    }
+    for entry in entries_b.keys() {
+        if !entries_a.contains_key(entry) {
+            lines.push(format!("{} {}", "+".green().bold(), entry));
+            changes += 1;
+        }
+    }
+
+    if !lines.is_empty() {
+        println!("{} {}", "~".yellow().bold(), name.bold());
+        for line in &lines {
+            println!("    {}", line);
+        }
+    }
+    Ok(changes)
+}
+
+/// The contents of every entry, decompressing compressed entries
+fn entries<'a>(pbo: &'a ReadablePBO) -> Result<BTreeMap<&'a str, Cow<'a, [u8]>>, HEMTTError> {
+    pbo.entries
+        .iter()
+        .map(|entry| Ok((entry.name.as_str(), contents(pbo, entry)?)))
+        .collect()
+}
+
+fn contents<'a>(pbo: &'a ReadablePBO, entry: &PBOEntry) -> Result<Cow<'a, [u8]>, HEMTTError> {
+    if entry.packing_method == COMPRESSED {
+        let data = decompress(pbo.data(entry), entry.original_size as usize)
+            .map_err(|e| HEMTTError::generic(format!("Unable to decompress `{}`", entry.name), e.to_string()))?;
+        Ok(Cow::Owned(data))
+    } else {
+        Ok(Cow::Borrowed(pbo.data(entry)))
+    }
+}
+
+/// Compares two sets of key value pairs, `label` is printed before each key, returning the number of differences
+fn diff_maps(a: &BTreeMap<&str, &str>, b: &BTreeMap<&str, &str>, label: &str, lines: &mut Vec<String>) -> usize {
+    let start = lines.len();
+    for (key, value) in a {
+        match b.get(key) {
+            None => lines.push(format!("{} {}{} = {}", "-".red().bold(), label, key, value)),
+            Some(other) if other != value => {
+                lines.push(format!("{} {}{}: {} -> {}", "~".yellow().bold(), label, key, value, other))
+            }
+            _ => {}
+        }
+    }
+    for (key, value) in b {
+        if !a.contains_key(key) {
+            lines.push(format!("{} {}{} = {}", "+".green().bold(), label, key, value));
+        }
+    }
+    lines.len() - start
+}
+
+/// Compares the classes of two configs, returning the number of classes that were added, removed or changed
+fn diff_classes(a: &Classes, b: &Classes, lines: &mut Vec<String>) -> usize {
+    let mut classes = 0;
+    for (class, properties) in a {
+        match b.get(class) {
+            None => {
+                lines.push(format!("    {} class {}", "-".red().bold(), class));
+                classes += 1;
+            }
+            Some(other) => {
+                let mut changes = Vec::new();
+                let properties: BTreeMap<&str, &str> = properties.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+                let other: BTreeMap<&str, &str> = other.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+                if diff_maps(&properties, &other, "", &mut changes) != 0 {
+                    lines.push(format!("    {} class {}", "~".yellow().bold(), class));
+                    lines.extend(changes.into_iter().map(|change| format!("        {}", change)));
+                    classes += 1;
+                }
+            }
+        }
+    }
+    for class in b.keys() {
+        if !a.contains_key(class) {
+            lines.push(format!("    {} class {}", "+".green().bold(), class));
+            classes += 1;
+        }
+    }
+    classes
+}
+
+fn derapify(data: &[u8]) -> Result<armake2::Config, HEMTTError> {
+    Ok(armake2::Config::read_rapified(&mut Cursor::new(data))?)
+}
+
+/// Reads the classes and properties of a config, inheritance is stored as the `:` property
+fn classes(config: &armake2::Config) -> Classes {
+    let mut classes = Classes::new();
+    if let Some(entries) = &config.root_body.entries {
+        walk(entries, &mut Vec::new(), &mut classes);
+    }
+    classes
+}
+
+fn walk(entries: &[(String, ConfigEntry)], path: &mut Vec<String>, classes: &mut Classes) {
+    for (name, entry) in entries {
+        if let ConfigEntry::ClassEntry(class) = entry {
+            path.push(name.clone());
+            let properties = classes.entry(path.join("/")).or_insert_with(BTreeMap::new);
+            if !class.parent.is_empty() {
+                properties.insert(":".to_string(), class.parent.clone());
+            }
+            if let Some(entries) = &class.entries {
+                walk(entries, path, classes);
+            }
+            path.pop();
+        } else if !path.is_empty() {
+            let (property, value) = match entry {
+                ConfigEntry::ArrayEntry(array) if array.is_expansion => (format!("{}[] +", name), value(entry)),
+                ConfigEntry::ArrayEntry(_) => (format!("{}[]", name), value(entry)),
+                _ => (name.clone(), value(entry)),
+            };
+            classes
+                .entry(path.join("/"))
+                .or_insert_with(BTreeMap::new)
+                .insert(property, value);
+        }
+    }
+}
+
+fn value(entry: &ConfigEntry) -> String {
+    match entry {
+        ConfigEntry::StringEntry(s) => format!("\"{}\"", s.replace('"', "\"\"")),
+        ConfigEntry::FloatEntry(f) => f.to_string(),
+        ConfigEntry::IntEntry(i) => i.to_string(),
+        ConfigEntry::ArrayEntry(array) => elements(array),
+        ConfigEntry::ClassEntry(_) => String::new(),
+    }
+}
+
+fn elements(array: &ConfigArray) -> String {
+    let elements: Vec<String> = array
+        .elements
+        .iter()
+        .map(|element| match element {
+            ConfigArrayElement::StringElement(s) => format!("\"{}\"", s.replace('"', "\"\"")),
+            ConfigArrayElement::FloatElement(f) => f.to_string(),
+            ConfigArrayElement::IntElement(i) => i.to_string(),
+            ConfigArrayElement::ArrayElement(array) => elements(array),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn parse(config: &str) -> Classes {
+        let config = armake2::Config::from_string(config.to_string(), None, &Vec::<PathBuf>::new(), |_| String::new());
+        classes(&config.unwrap())
+    }
+
+    #[test]
+    fn nested_classes() {
+        let classes = parse(
+            r#"
+class CfgPatches {
+    class main {
+        units[] = {
+            "tst_car",
+            "tst_tank"
+        };
+        requiredVersion = 1.9;
+    };
+};
+class CfgVehicles {
+    class Car;
+    class Tank: Car {
+        speed = 10;
+    };
+};
+"#,
+        );
+        assert_eq!(
+            vec![
+                "CfgPatches",
+                "CfgPatches/main",
+                "CfgVehicles",
+                "CfgVehicles/Car",
+                "CfgVehicles/Tank"
+            ],
+            classes.keys().collect::<Vec<_>>()
+        );
+        let main = &classes["CfgPatches/main"];
+        assert_eq!("{\"tst_car\", \"tst_tank\"}", main["units[]"]);
+        assert_eq!("1.9", main["requiredVersion"]);
+        let tank = &classes["CfgVehicles/Tank"];
+        assert_eq!("Car", tank[":"]);
+        assert_eq!("10", tank["speed"]);
+    }
+
+    #[test]
+    fn changes() {
+        let a = parse("class CfgVehicles { class Car { speed = 10; }; class Tank { speed = 5; }; };");
+        let b = parse("class CfgVehicles { class Car { speed = 20; }; class Plane { speed = 50; }; };");
+        let mut lines = Vec::new();
+        // The changed class, the removed class and the added class
+        assert_eq!(3, diff_classes(&a, &b, &mut lines));
+        // The changed class is followed by its property
+        assert_eq!(4, lines.len());
+    }
+}
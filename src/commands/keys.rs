@@ -0,0 +1,186 @@
+use std::path::{Path, PathBuf};
+
+use armake2::{BIPrivateKey, BIPublicKey};
+use colored::*;
+
+use crate::{Command, HEMTTError, Project};
+
+pub struct Keys {}
+impl Command for Keys {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("keys")
+            .version(*crate::VERSION)
+            .about("Manage the keys used to sign releases")
+            .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                clap::SubCommand::with_name("generate")
+                    .about("Generate the private key and its public key")
+                    .arg(
+                        clap::Arg::with_name("force")
+                            .help("Replace an existing private key")
+                            .long("force")
+                            .short("f"),
+                    ),
+            )
+            .subcommand(clap::SubCommand::with_name("list").about("List the private key and the public keys"))
+            .subcommand(
+                clap::SubCommand::with_name("export")
+                    .about("Write the public key of the private key")
+                    .arg(clap::Arg::with_name("output").help("Destination, defaults to `keys/{key_name}.bikey`")),
+            )
+            .subcommand(clap::SubCommand::with_name("rotate").about("Back up the private key and generate a new one"))
+    }
+
+    fn run(&self, args: &clap::ArgMatches, p: Project) -> Result<(), HEMTTError> {
+        match args.subcommand() {
+            ("generate", Some(sub)) => generate(&p, sub.is_present("force")),
+            ("list", Some(_)) => list(&p),
+            ("export", Some(sub)) => export(&p, sub.value_of("output")),
+            ("rotate", Some(_)) => rotate(&p),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Reads a .biprivatekey
+pub fn read_private_key(path: &Path) -> Result<BIPrivateKey, HEMTTError> {
+    BIPrivateKey::read(&mut open_file!(path)?)
+        .map_err(|e| HEMTTError::generic(format!("Unable to read `{}`", path.display()), format!("{:?}", e)))
+}
+
+/// Reads a base64 encoded .biprivatekey from an environment variable, the key is only kept in memory
+pub fn read_env_private_key(var: &str) -> Result<BIPrivateKey, HEMTTError> {
+    let encoded = std::env::var(var).map_err(|_| {
+        HEMTTError::generic(
+            format!("The environment variable `{}` is not set", var),
+            "It should contain the base64 encoded .biprivatekey",
+        )
+    })?;
+    let encoded: String = encoded.chars().filter(|c| !c.is_whitespace()).collect();
+    let data =
+        base64::decode(&encoded).map_err(|e| HEMTTError::generic(format!("Unable to decode `{}`", var), e.to_string()))?;
+    BIPrivateKey::read(&mut std::io::Cursor::new(data))
+        .map_err(|e| HEMTTError::generic(format!("Unable to read the private key in `{}`", var), format!("{:?}", e)))
+}
+
+/// Reads a .bikey
+pub fn read_public_key(path: &Path) -> Result<BIPublicKey, HEMTTError> {
+    BIPublicKey::read(&mut open_file!(path)?)
+        .map_err(|e| HEMTTError::generic(format!("Unable to read `{}`", path.display()), format!("{:?}", e)))
+}
+
+fn generate(p: &Project, force: bool) -> Result<(), HEMTTError> {
+    let path = p.get_private_key_path()?;
+    if path.exists() && !force {
+        return Err(HEMTTError::generic(
+            format!("`{}` already exists", path.display()),
+            "Use `--force` to replace it or `hemtt keys rotate` to keep a backup",
+        ));
+    }
+    write_key(p, &path)
+}
+
+/// The private key used to sign releases, from `private_key_env` when it is set
+fn private_key(p: &Project) -> Result<BIPrivateKey, HEMTTError> {
+    if p.private_key_env.is_empty() {
+        read_private_key(&p.get_private_key_path()?)
+    } else {
+        read_env_private_key(&p.private_key_env)
+    }
+}
+
+fn list(p: &Project) -> Result<(), HEMTTError> {
+    let path = p.get_private_key_path()?;
+    println!("{}", "Private Key".bold());
+    if !p.private_key_env.is_empty() {
+        let key = read_env_private_key(&p.private_key_env)?;
+        println!(
+            "    {} {} bits  ${}",
+            fill_space!(" ", 24, &key.name),
+            key.length,
+            p.private_key_env
+        );
+    } else if path.exists() {
+        let key = read_private_key(&path)?;
+        println!(
+            "    {} {} bits  {}",
+            fill_space!(" ", 24, &key.name),
+            key.length,
+            path.display()
+        );
+    } else {
+        println!("    {} {}", fill_space!(" ", 24, "missing"), path.display());
+    }
+    println!();
+
+    println!("{}", "Public Keys".bold());
+    let mut keys: Vec<PathBuf> = Vec::new();
+    if Path::new("keys").exists() {
+        for entry in std::fs::read_dir("keys")? {
+            let entry = entry?.path();
+            if entry.extension().unwrap_or_default() == "bikey" {
+                keys.push(entry);
+            }
+        }
+    }
+    keys.sort();
+    for entry in keys {
+        let key = read_public_key(&entry)?;
+        println!(
+            "    {} {} bits  {}",
+            fill_space!(" ", 24, &key.name),
+            key.length,
+            entry.display()
+        );
+    }
+    Ok(())
+}
+
+fn export(p: &Project, output: Option<&str>) -> Result<(), HEMTTError> {
+    let key = private_key(p)?;
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => {
+            create_dir!("keys/")?;
+            PathBuf::from(format!("keys/{}.bikey", key.name))
+        }
+    };
+    key.to_public_key().write(&mut create_file!(output)?)?;
+    println!("{} `{}`", "Exported".green().bold(), output.display());
+    Ok(())
+}
+
+fn rotate(p: &Project) -> Result<(), HEMTTError> {
+    if !p.private_key_env.is_empty() {
+        return Err(HEMTTError::generic(
+            format!("The private key is read from `{}`", p.private_key_env),
+            "Generate a new key elsewhere and replace the value of the environment variable",
+        ));
+    }
+    let path = p.get_private_key_path()?;
+    if path.exists() {
+        let backup = PathBuf::from(format!("{}.{}", path.display(), chrono::Utc::now().format("%Y%m%d%H%M%S")));
+        rename_file!(path, backup)?;
+        println!("{} `{}` to `{}`", "Moved".green().bold(), path.display(), backup.display());
+    }
+    write_key(p, &path)
+}
+
+/// Generates a private key at `path` and writes its public key to `keys/`
+fn write_key(p: &Project, path: &Path) -> Result<(), HEMTTError> {
+    let key_name = p.get_key_name()?;
+    let key = BIPrivateKey::generate(p.get_key_size()?, key_name.clone());
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            create_dir!(parent)?;
+        }
+    }
+    key.write(&mut create_file!(path)?)?;
+    println!("{} `{}`", "Generated".green().bold(), path.display());
+
+    create_dir!("keys/")?;
+    let public = format!("keys/{}.bikey", key_name);
+    key.to_public_key().write(&mut create_file!(public)?)?;
+    println!("{} `{}`", "Generated".green().bold(), public);
+    Ok(())
+}
@@ -19,6 +19,30 @@ pub use status::Status;
 mod update;
 pub use update::Update;
 
+mod watch;
+pub use watch::Watch;
+
+mod pbo;
+pub use pbo::Pbo;
+
+mod diff;
+pub use diff::Diff;
+
+pub mod verify;
+pub use verify::Verify;
+
+pub mod keys;
+pub use keys::Keys;
+
+mod sign;
+pub use sign::Sign;
+
+pub mod changelog;
+pub use changelog::Changelog;
+
+mod version;
+pub use version::Version;
+
 use crate::{HEMTTError, Project};
 
 pub trait Command {
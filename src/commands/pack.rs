@@ -1,18 +1,17 @@
-use std::path::Path;
-
-use crate::{AddonLocation, Command, Flow, HEMTTError, Project, Stage, Step};
+use crate::{Command, Flow, HEMTTError, Project, Stage, Step};
 
 pub struct Pack {}
 impl Command for Pack {
     fn register(&self) -> clap::App {
-        clap::SubCommand::with_name("pack")
+        let sub = clap::SubCommand::with_name("pack")
             .version(*crate::VERSION)
             .about("Pack the Project")
             .arg(
                 clap::Arg::with_name("release")
                     .help("Pack a release")
                     .long("release")
-                    .conflicts_with("dev"),
+                    .conflicts_with("dev")
+                    .conflicts_with_all(&["addon", "exclude-addon", "location"]),
             )
             .arg(
                 clap::Arg::with_name("clear")
@@ -26,16 +25,28 @@ impl Command for Pack {
                     .help("Remove an existing release")
                     .long("force-release"),
             )
+            .arg(
+                clap::Arg::with_name("keep-going")
+                    .help("Continue building other addons when an addon fails")
+                    .long("keep-going"),
+            )
+            .arg(
+                clap::Arg::with_name("dry-run")
+                    .help("Show what would be done without changing any files")
+                    .long("dry-run"),
+            )
+            .arg(
+                clap::Arg::with_name("verify-reproducible")
+                    .help("Build each PBO twice and fail if the results differ")
+                    .long("verify-reproducible"),
+            )
+            .arg(crate::output::report_arg());
+        crate::build::addon_args(sub)
     }
 
     fn run(&self, args: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
-        let mut addons = crate::build::get_addons(AddonLocation::Addons)?;
-        if Path::new(&AddonLocation::Optionals.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Optionals)?);
-        }
-        if Path::new(&AddonLocation::Compats.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Compats)?);
-        }
+        let addons = crate::build::get_selected_addons(args)?;
+        let reports = crate::output::targets(args)?;
         let flow = Flow {
             steps: vec![
                 Step::single(
@@ -62,14 +73,23 @@ impl Command for Pack {
                         Box::new(crate::build::prebuild::render::Render {}),
                         Box::new(crate::build::checks::names::NotEmpty {}),
                         Box::new(crate::build::checks::names::ValidName {}),
-                        Box::new(crate::build::checks::modtime::ModTime {}),
+                        Box::new(crate::build::checks::cache::Cache { use_bin: false }),
                     ],
                 ),
                 Step::parallel(
                     "📦",
                     "Pack",
                     Stage::Build,
-                    vec![Box::new(crate::build::build::Build::new(false))],
+                    vec![Box::new(crate::build::build::Build::new(
+                        false,
+                        args.is_present("verify-reproducible"),
+                    ))],
+                ),
+                Step::single(
+                    "💾",
+                    "",
+                    Stage::Build,
+                    vec![Box::new(crate::build::postbuild::cache::SaveCache {})],
                 ),
                 if args.is_present("release") {
                     Step::single(
@@ -83,14 +103,40 @@ impl Command for Pack {
                 } else {
                     Step::none()
                 },
+                if args.is_present("release") && p.changelog.is_some() {
+                    Step::single(
+                        "⭐",
+                        "Changelog",
+                        Stage::ReleaseBuild,
+                        vec![Box::new(crate::build::postbuild::changelog::Changelog {})],
+                    )
+                } else {
+                    Step::none()
+                },
+                if args.is_present("release") {
+                    Step::single(
+                        "⭐",
+                        "Manifest",
+                        Stage::ReleaseBuild,
+                        vec![Box::new(crate::build::postbuild::manifest::WriteManifest {})],
+                    )
+                } else {
+                    Step::none()
+                },
                 if args.is_present("release") {
                     Step::single("📜", "", Stage::ReleaseBuild, vec![Box::new(crate::flow::Script {})])
                 } else {
                     Step::none()
                 },
             ],
+            keep_going: args.is_present("keep-going"),
+            dry_run: args.is_present("dry-run"),
+            time: args.is_present("time"),
+            events: crate::flow::default_events(args.is_present("dry-run")),
         };
-        flow.execute(addons, &mut p)?;
-        Ok(())
+        let (addons, result) = flow.execute(addons, &mut p);
+        crate::output::write(&reports, &addons, &p)?;
+        result?;
+        flow.summary(&addons)
     }
 }
@@ -0,0 +1,63 @@
+use colored::*;
+
+use crate::pbo::ReadablePBO;
+use crate::HEMTTError;
+
+pub fn info(file: &str) -> Result<(), HEMTTError> {
+    let pbo = ReadablePBO::read(&mut open_file!(file)?)?;
+
+    println!("{}", "Header Extensions".bold());
+    for (key, value) in &pbo.extensions {
+        println!("    {} = {}", fill_space!(" ", 16, key), value);
+    }
+    println!();
+
+    println!("{}", "Files".bold());
+    println!(
+        "    {} {} {} {} {}",
+        fill_space!(" ", 10, "Offset"),
+        fill_space!(" ", 10, "Size"),
+        fill_space!(" ", 10, "Original"),
+        fill_space!(" ", 10, "Method"),
+        "Name"
+    );
+    for entry in &pbo.entries {
+        println!(
+            "    {} {} {} {} {}",
+            fill_space!(" ", 10, &entry.offset.to_string()),
+            fill_space!(" ", 10, &entry.data_size.to_string()),
+            fill_space!(" ", 10, &entry.original_size.to_string()),
+            fill_space!(" ", 10, &method(entry.packing_method)),
+            entry.name
+        );
+    }
+    let total: u64 = pbo.entries.iter().map(|entry| u64::from(entry.data_size)).sum();
+    println!("    {} files, {} bytes", pbo.entries.len(), total);
+    println!();
+
+    println!("{}", "Checksum".bold());
+    match pbo.checksum_valid() {
+        Some(true) => println!("    {} {}", hex(pbo.checksum.as_ref().unwrap()), "valid".green().bold()),
+        Some(false) => {
+            println!("    {} {}", hex(pbo.checksum.as_ref().unwrap()), "invalid".red().bold());
+            return Err(HEMTTError::generic(
+                format!("The checksum of `{}` does not match its contents", file),
+                format!("The contents hash to {}", hex(&pbo.computed)),
+            ));
+        }
+        None => println!("    {}", "The PBO has no checksum".yellow()),
+    }
+    Ok(())
+}
+
+fn method(packing_method: u32) -> String {
+    match packing_method {
+        0 => "none".to_owned(),
+        0x4370_7273 => "compressed".to_owned(),
+        _ => format!("{:#010x}", packing_method),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
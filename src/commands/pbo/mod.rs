@@ -0,0 +1,46 @@
+use crate::{Command, HEMTTError};
+
+mod info;
+mod unpack;
+
+pub struct Pbo {}
+impl Command for Pbo {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("pbo")
+            .version(*crate::VERSION)
+            .about("Inspect and unpack PBO files")
+            .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                clap::SubCommand::with_name("info")
+                    .about("List the header extensions, files and checksum of a PBO")
+                    .arg(clap::Arg::with_name("file").help("PBO to inspect").required(true)),
+            )
+            .subcommand(
+                clap::SubCommand::with_name("unpack")
+                    .about("Extract the files of a PBO into a folder")
+                    .arg(clap::Arg::with_name("file").help("PBO to unpack").required(true))
+                    .arg(clap::Arg::with_name("output").help("Folder to extract to, defaults to the name of the PBO"))
+                    .arg(
+                        clap::Arg::with_name("derapify")
+                            .help("Convert config.bin back into config.cpp")
+                            .long("derapify"),
+                    ),
+            )
+    }
+
+    fn require_project(&self) -> bool {
+        false
+    }
+
+    fn run_no_project(&self, args: &clap::ArgMatches) -> Result<(), HEMTTError> {
+        match args.subcommand() {
+            ("info", Some(sub)) => info::info(sub.value_of("file").unwrap()),
+            ("unpack", Some(sub)) => unpack::unpack(
+                sub.value_of("file").unwrap(),
+                sub.value_of("output"),
+                sub.is_present("derapify"),
+            ),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use colored::*;
+
+use crate::pbo::{decompress, ReadablePBO, COMPRESSED};
+use crate::HEMTTError;
+
+pub fn unpack(file: &str, output: Option<&str>, derapify: bool) -> Result<(), HEMTTError> {
+    let pbo = ReadablePBO::read(&mut open_file!(file)?)?;
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let stem = Path::new(file).file_stem().ok_or_else(|| {
+                HEMTTError::generic(format!("`{}` is not a file", file), "Provide the folder to unpack to")
+            })?;
+            PathBuf::from(stem)
+        }
+    };
+    if output.exists() {
+        return Err(HEMTTError::generic(
+            format!("`{}` already exists", output.display()),
+            "Remove it or choose another folder",
+        ));
+    }
+    create_dir!(output)?;
+
+    // Lines of `$PBOPREFIX$` are read back into header extensions when building
+    if !pbo.extensions.is_empty() {
+        let mut prefix = String::new();
+        if let Some(value) = pbo.extension("prefix") {
+            prefix.push_str(&format!("{}\n", value));
+        }
+        for (key, value) in &pbo.extensions {
+            if key != "prefix" {
+                prefix.push_str(&format!("{}={}\n", key, value));
+            }
+        }
+        create_file!(output.join("$PBOPREFIX$"))?.write_all(prefix.as_bytes())?;
+    }
+
+    for entry in &pbo.entries {
+        let mut path = output.clone();
+        for part in entry.name.split('\\') {
+            if part.is_empty() || part == "." || part == ".." {
+                continue;
+            }
+            path.push(part);
+        }
+        let invalid = || HEMTTError::generic(format!("`{}` is not a valid file name", entry.name), "The PBO is damaged");
+        if path == output {
+            return Err(invalid());
+        }
+        create_dir!(path.parent().ok_or_else(invalid)?)?;
+        let data = if entry.packing_method == COMPRESSED {
+            decompress(pbo.data(entry), entry.original_size as usize)
+                .map_err(|e| HEMTTError::generic(format!("Unable to decompress `{}`", entry.name), e.to_string()))?
+        } else {
+            pbo.data(entry).to_vec()
+        };
+        if derapify && path.file_name().ok_or_else(invalid)? == "config.bin" {
+            let config = armake2::Config::read_rapified(&mut Cursor::new(data.as_slice()))?;
+            path.set_file_name("config.cpp");
+            config.write(&mut create_file!(path)?)?;
+        } else {
+            create_file!(path)?.write_all(&data)?;
+        }
+    }
+
+    println!(
+        "{} {} files to {}",
+        "Unpacked".green().bold(),
+        pbo.entries.len(),
+        output.display()
+    );
+    Ok(())
+}
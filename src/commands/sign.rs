@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use armake2::{BISignVersion, PBO};
+use colored::*;
+use walkdir::WalkDir;
+
+use crate::{Command, HEMTTError};
+
+pub struct Sign {}
+impl Command for Sign {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("sign")
+            .version(*crate::VERSION)
+            .about("Sign every PBO in a mod folder")
+            .arg(clap::Arg::with_name("folder").help("Mod folder to sign").required(true))
+            .arg(
+                clap::Arg::with_name("key")
+                    .help("Private key to sign with")
+                    .long("key")
+                    .short("k")
+                    .takes_value(true)
+                    .required(true),
+            )
+            .arg(
+                clap::Arg::with_name("sig-version")
+                    .help("Signature version")
+                    .long("sig-version")
+                    .takes_value(true)
+                    .possible_values(&["2", "3"])
+                    .default_value("3"),
+            )
+            .arg(
+                clap::Arg::with_name("dry-run")
+                    .help("Show what would be done without changing any files")
+                    .long("dry-run"),
+            )
+    }
+
+    fn require_project(&self) -> bool {
+        false
+    }
+
+    fn run_no_project(&self, args: &clap::ArgMatches) -> Result<(), HEMTTError> {
+        sign(
+            Path::new(args.value_of("folder").unwrap()),
+            Path::new(args.value_of("key").unwrap()),
+            args.value_of("sig-version").unwrap() == "2",
+            args.is_present("dry-run"),
+        )
+    }
+}
+
+/// Signs the PBOs of a mod folder as `<pbo>.<key>.bisign` and adds the public key to its `keys` folder
+pub fn sign(folder: &Path, key: &Path, v2: bool, dry_run: bool) -> Result<(), HEMTTError> {
+    if !folder.is_dir() {
+        return Err(HEMTTError::generic(
+            format!("`{}` is not a folder", folder.display()),
+            "Provide the `@mod` folder to sign",
+        ));
+    }
+    let key = crate::commands::keys::read_private_key(key)?;
+
+    let mut pbos: Vec<PathBuf> = WalkDir::new(folder)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().to_path_buf())
+        .filter(|p| p.is_file() && p.extension().unwrap_or_default() == "pbo")
+        .collect();
+    if pbos.is_empty() {
+        return Err(HEMTTError::simple(format!("No PBOs were found in `{}`", folder.display())));
+    }
+    pbos.sort();
+
+    for path in &pbos {
+        let pbo = PBO::read(&mut open_file!(path)?)?;
+        let mut location = path.clone();
+        location.set_file_name(format!("{}.{}.bisign", path.file_name().unwrap().to_string_lossy(), key.name));
+        if dry_run {
+            dryrun!("Would sign `{}` as `{}`", path.display(), location.display());
+            continue;
+        }
+        let version = if v2 { BISignVersion::V2 } else { BISignVersion::V3 };
+        key.sign(&pbo, version).write(&mut create_file!(location)?)?;
+        debug!("Signed `{}`", path.display());
+    }
+
+    let keys = folder.join("keys");
+    let public = keys.join(format!("{}.bikey", key.name));
+    if dry_run {
+        dryrun!("Would write `{}`", public.display());
+        return Ok(());
+    }
+    create_dir!(keys)?;
+    key.to_public_key().write(&mut create_file!(public)?)?;
+    println!("{} {} PBOs with `{}`", "Signed".green().bold(), pbos.len(), key.name);
+    Ok(())
+}
@@ -1,6 +1,4 @@
-use std::path::Path;
-
-use crate::{AddonLocation, Command, Flow, HEMTTError, Project, Stage, Step};
+use crate::{Command, Flow, HEMTTError, Project, Stage, Step};
 
 pub struct Status {}
 impl Command for Status {
@@ -8,16 +6,12 @@ impl Command for Status {
         clap::SubCommand::with_name("status")
             .version(*crate::VERSION)
             .about("Get the status of your project")
+            .arg(crate::output::report_arg())
     }
 
-    fn run(&self, _: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
-        let mut addons = crate::build::get_addons(AddonLocation::Addons)?;
-        if Path::new(&AddonLocation::Optionals.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Optionals)?);
-        }
-        if Path::new(&AddonLocation::Compats.to_string()).exists() {
-            addons.extend(crate::build::get_addons(AddonLocation::Compats)?);
-        }
+    fn run(&self, args: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
+        let addons = crate::build::get_all_addons()?;
+        let reports = crate::output::targets(args)?;
         let flow = Flow {
             steps: vec![Step::parallel(
                 "🔍",
@@ -27,16 +21,23 @@ impl Command for Status {
                     Box::new(crate::build::prebuild::render::Render {}),
                     Box::new(crate::build::checks::names::NotEmpty {}),
                     Box::new(crate::build::checks::names::ValidName {}),
-                    Box::new(crate::build::checks::modtime::ModTime {}),
+                    Box::new(crate::build::checks::cache::Cache { use_bin: true }),
                 ],
             )],
+            keep_going: false,
+            dry_run: false,
+            time: args.is_present("time"),
+            events: crate::flow::default_events(false),
         };
-        let addons = flow.execute(addons, &mut p)?;
-        let mut build = 0;
+        let (addons, result) = flow.execute(addons, &mut p);
+        crate::output::write(&reports, &addons, &p)?;
+        result?;
+        flow.summary(&addons)?;
+        let mut build = Vec::new();
         for addon in addons {
-            let (report, _) = addon?;
+            let (report, addon) = addon?;
             if report.stop.is_none() {
-                build += 1
+                build.push((addon, report.rebuild));
             }
         }
         println!("CI Environment: {}", crate::is_ci());
@@ -44,7 +45,14 @@ impl Command for Status {
             "Version: {}",
             p.version().unwrap_or_else(|_| "Unable to determine".to_string())
         );
-        println!("Addons to be built: {}", build);
+        println!("Addons to be built: {}", build.len());
+        for (addon, reason) in build {
+            if let Some(reason) = reason {
+                println!("    {} - {}", addon.name, reason);
+            } else {
+                println!("    {}", addon.name);
+            }
+        }
         Ok(())
     }
 }
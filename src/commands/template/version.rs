@@ -4,10 +4,17 @@ use std::path::{Path, PathBuf};
 use super::Template;
 use crate::HEMTTError;
 
+static GET_VERSION: &str = "./.hemtt/template/scripts/get_version.lua";
+
 impl Template {
+    /// Whether the template has a script that decides the version
+    pub fn has_version_script(&self) -> bool {
+        PathBuf::from(GET_VERSION).exists()
+    }
+
     pub fn get_version(&self) -> Result<String, HEMTTError> {
-        if PathBuf::from("./.hemtt/template/scripts/get_version.lua").exists() {
-            Ok(self.eval_file("./.hemtt/template/scripts/get_version.lua", |_| {}))
+        if self.has_version_script() {
+            Ok(self.eval_file(GET_VERSION, |_| {}))
         } else if Path::new("addons/main/script_version.hpp").exists() {
             let f = BufReader::new(open_file!("addons/main/script_version.hpp")?);
             let (mut major, mut minor, mut patch, mut build) = (0, 0, 0, String::new());
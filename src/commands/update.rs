@@ -1,4 +1,3 @@
-use crate::error::PrintableError;
 use crate::{Command, HEMTTError};
 
 pub struct Update {}
@@ -28,9 +27,8 @@ impl Command for Update {
             .show_download_progress(true)
             .current_version(env!("CARGO_PKG_VERSION"))
             .build()
-            .unwrap_or_print()
-            .update()
-            .unwrap_or_print();
+            .and_then(|update| update.update())
+            .map_err(|e| HEMTTError::generic("Unable to update HEMTT", e.to_string()))?;
         println!("\nUsing Version: {}", status.version());
         Ok(())
     }
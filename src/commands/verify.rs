@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use armake2::{BIPublicKey, BISign, PBO};
+use colored::*;
+use walkdir::WalkDir;
+
+use crate::{Command, HEMTTError, Project};
+
+pub struct Verify {}
+impl Command for Verify {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("verify")
+            .version(*crate::VERSION)
+            .about("Verify the signatures of a release")
+            .arg(clap::Arg::with_name("release").help("Release folder, defaults to the current version"))
+            .arg(
+                clap::Arg::with_name("manifest")
+                    .help("Check the files against the release manifest instead of the signatures")
+                    .long("manifest"),
+            )
+    }
+
+    fn require_project(&self) -> bool {
+        false
+    }
+
+    fn run_no_project(&self, args: &clap::ArgMatches) -> Result<(), HEMTTError> {
+        // Reading the project changes to its root, so a given release is resolved first
+        let release = args
+            .value_of("release")
+            .map(|release| std::env::current_dir().map(|dir| dir.join(release)))
+            .transpose()?;
+        // Only the keys of the project are trusted, a key in the release could have been copied in with the signatures
+        let (release, keys) = if crate::project::find_root().is_ok() {
+            let p = Project::read()?;
+            let release = match release {
+                Some(release) => release,
+                None => p.release_dir()?,
+            };
+            (release, PathBuf::from("keys"))
+        } else {
+            let release = match release {
+                Some(release) => release,
+                None => Project::read()?.release_dir()?,
+            };
+            let keys = release.join("keys");
+            (release, keys)
+        };
+        if args.is_present("manifest") {
+            crate::build::postbuild::manifest::check(&release)
+        } else {
+            verify(&release, &keys)
+        }
+    }
+}
+
+/// Checks that every PBO in the release is signed by one of the keys in `keys_folder`
+pub fn verify(release: &Path, keys_folder: &Path) -> Result<(), HEMTTError> {
+    let keys = read_keys(keys_folder)?;
+    if keys.is_empty() {
+        return Err(HEMTTError::generic(
+            "Unable to verify the release",
+            format!("No keys were found in `{}`", keys_folder.display()),
+        ));
+    }
+
+    let mut problems = 0;
+    let mut verified = 0;
+    for entry in WalkDir::new(release) {
+        let entry = entry.map_err(std::io::Error::from)?;
+        let path = entry.path();
+        if !path.is_file() || path.extension().unwrap_or_default() != "pbo" {
+            continue;
+        }
+        let pbo = PBO::read(&mut open_file!(path)?)?;
+        let signatures = signatures(path)?;
+        if signatures.is_empty() {
+            error!("`{}` is not signed", path.display());
+            problems += 1;
+        }
+        for signature in signatures {
+            let sign = BISign::read(&mut open_file!(signature)?)
+                .map_err(|e| HEMTTError::generic(format!("Unable to read `{}`", signature.display()), format!("{:?}", e)))?;
+            match keys.iter().find(|key| key.name == sign.name) {
+                None => {
+                    errormessage!(
+                        format!("`{}` was signed with an unknown key", signature.display()),
+                        format!("`{}.bikey` is not in `{}`", sign.name, keys_folder.display())
+                    );
+                    problems += 1;
+                }
+                Some(key) => {
+                    if key.verify(&pbo, &sign).is_ok() {
+                        verified += 1;
+                    } else {
+                        errormessage!(
+                            format!("`{}` does not match `{}`", signature.display(), path.display()),
+                            "The PBO changed after it was signed, or it was signed with another key of the same name"
+                        );
+                        problems += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    if problems > 0 {
+        return Err(HEMTTError::simple(format!("Found {} signature problem(s)", problems)));
+    }
+    println!("{} {} signatures", "Verified".green().bold(), verified);
+    Ok(())
+}
+
+fn read_keys(folder: &Path) -> Result<Vec<BIPublicKey>, HEMTTError> {
+    let mut keys = Vec::new();
+    if !folder.exists() {
+        return Ok(keys);
+    }
+    for entry in std::fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().unwrap_or_default() == "bikey" {
+            keys.push(
+                BIPublicKey::read(&mut open_file!(path)?)
+                    .map_err(|e| HEMTTError::generic(format!("Unable to read `{}`", path.display()), format!("{:?}", e)))?,
+            );
+        }
+    }
+    Ok(keys)
+}
+
+/// Signatures next to a PBO, named `<pbo>.<key>.bisign`
+fn signatures(pbo: &Path) -> Result<Vec<PathBuf>, HEMTTError> {
+    let prefix = format!("{}.", pbo.file_name().unwrap().to_string_lossy());
+    let mut signatures = Vec::new();
+    for entry in std::fs::read_dir(pbo.parent().unwrap())? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        if name.starts_with(&prefix) && name.ends_with(".bisign") {
+            signatures.push(path);
+        }
+    }
+    signatures.sort();
+    Ok(signatures)
+}
@@ -0,0 +1,360 @@
+use std::path::Path;
+
+use colored::*;
+use git2::{Commit, Index, Repository, StatusOptions, Tree};
+use regex::{Captures, Regex};
+
+use crate::{Command, HEMTTError, Project};
+
+static SCRIPT_VERSION: &str = "addons/main/script_version.hpp";
+
+pub struct Version {}
+impl Command for Version {
+    fn register(&self) -> clap::App {
+        clap::SubCommand::with_name("version")
+            .version(*crate::VERSION)
+            .about("Show or change the version of the project")
+            .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(clap::SubCommand::with_name("show").about("Show the version of the project"))
+            .subcommand(
+                git_args(clap::SubCommand::with_name("bump").about("Increase a part of the version")).arg(
+                    clap::Arg::with_name("part")
+                        .help("Part of the version to increase")
+                        .possible_values(&["major", "minor", "patch", "build"])
+                        .required(true),
+                ),
+            )
+            .subcommand(
+                git_args(clap::SubCommand::with_name("set").about("Set the version")).arg(
+                    clap::Arg::with_name("version")
+                        .help("Version as `x.y.z` or `x.y.z.b`")
+                        .required(true),
+                ),
+            )
+    }
+
+    fn run(&self, args: &clap::ArgMatches, p: Project) -> Result<(), HEMTTError> {
+        match args.subcommand() {
+            ("show", Some(_)) => {
+                println!("{}", p.version()?);
+                Ok(())
+            }
+            ("bump", Some(sub)) => {
+                check_source(&p)?;
+                let mut version = ScriptVersion::read()?;
+                version.bump(sub.value_of("part").unwrap());
+                write(&version, sub)
+            }
+            ("set", Some(sub)) => {
+                check_source(&p)?;
+                let mut version = ScriptVersion::read()?;
+                version.set(sub.value_of("version").unwrap())?;
+                write(&version, sub)
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn git_args<'a, 'b>(sub: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
+    sub.arg(
+        clap::Arg::with_name("commit")
+            .help("Commit the changed version")
+            .long("commit"),
+    )
+    .arg(
+        clap::Arg::with_name("tag")
+            .help("Commit and tag the changed version")
+            .long("tag"),
+    )
+    .arg(
+        clap::Arg::with_name("force")
+            .help("Change the version even if the working tree has uncommitted changes")
+            .long("force")
+            .short("f"),
+    )
+}
+
+/// Only a version read from `script_version.hpp` can be changed, builds would not use a changed file otherwise
+fn check_source(p: &Project) -> Result<(), HEMTTError> {
+    match source(
+        &p.version,
+        &p.version_source,
+        crate::commands::Template::new().has_version_script(),
+    ) {
+        Some(source) => Err(HEMTTError::generic(
+            format!("The version is set by {}", source),
+            format!("Only a version from `{}` can be changed", SCRIPT_VERSION),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// What sets the version instead of `script_version.hpp`, in the order `Project::version` checks them
+fn source(version: &str, version_source: &str, script: bool) -> Option<&'static str> {
+    if !version.is_empty() {
+        Some("`version` in the project or `APP_VERSION`")
+    } else if !version_source.is_empty() {
+        Some("`version_source` in the project")
+    } else if script {
+        Some("the `get_version.lua` script of the template")
+    } else {
+        None
+    }
+}
+
+/// Version defines of `script_version.hpp`
+#[derive(Debug, PartialEq)]
+struct ScriptVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    /// `None` if the file does not define `BUILD`
+    build: Option<u32>,
+}
+
+impl ScriptVersion {
+    fn regex() -> Regex {
+        Regex::new(r"(?m)^(#define\s+(MAJOR|MINOR|PATCHLVL|PATCH|BUILD)\s+)(\d+)").unwrap()
+    }
+
+    fn read() -> Result<Self, HEMTTError> {
+        if !Path::new(SCRIPT_VERSION).exists() {
+            return Err(HEMTTError::generic(
+                format!("`{}` does not exist", SCRIPT_VERSION),
+                "The version can only be changed in `script_version.hpp`",
+            ));
+        }
+        Self::parse(&std::fs::read_to_string(SCRIPT_VERSION)?)
+    }
+
+    fn parse(content: &str) -> Result<Self, HEMTTError> {
+        let mut version = Self {
+            major: 0,
+            minor: 0,
+            patch: 0,
+            build: None,
+        };
+        for c in Self::regex().captures_iter(content) {
+            let value = c[3].parse()?;
+            match &c[2] {
+                "MAJOR" => version.major = value,
+                "MINOR" => version.minor = value,
+                "BUILD" => version.build = Some(value),
+                _ => version.patch = value,
+            }
+        }
+        Ok(version)
+    }
+
+    fn bump(&mut self, part: &str) {
+        match part {
+            "major" => {
+                self.major += 1;
+                self.minor = 0;
+                self.patch = 0;
+            }
+            "minor" => {
+                self.minor += 1;
+                self.patch = 0;
+            }
+            "patch" => self.patch += 1,
+            _ => {}
+        }
+        self.build = match self.build {
+            Some(build) if part == "build" => Some(build + 1),
+            Some(_) => Some(0),
+            None if part == "build" => Some(1),
+            None => None,
+        };
+    }
+
+    fn set(&mut self, version: &str) -> Result<(), HEMTTError> {
+        let parts: Vec<&str> = version.split('.').collect();
+        if parts.len() < 3 || parts.len() > 4 {
+            return Err(HEMTTError::generic(
+                format!("Invalid version `{}`", version),
+                "Use `x.y.z` or `x.y.z.b`",
+            ));
+        }
+        self.major = parts[0].parse()?;
+        self.minor = parts[1].parse()?;
+        self.patch = parts[2].parse()?;
+        if let Some(build) = parts.get(3) {
+            self.build = Some(build.parse()?);
+        } else if self.build.is_some() {
+            self.build = Some(0);
+        }
+        Ok(())
+    }
+
+    fn write(&self) -> Result<(), HEMTTError> {
+        let content = self.rewrite(&std::fs::read_to_string(SCRIPT_VERSION)?)?;
+        std::fs::write(SCRIPT_VERSION, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Replaces the numbers of the defines, keeping the rest of the file as is
+    fn rewrite(&self, content: &str) -> Result<String, HEMTTError> {
+        if self.build.is_some() && !content.lines().any(|l| l.split_whitespace().nth(1) == Some("BUILD")) {
+            return Err(HEMTTError::generic(
+                format!("`{}` does not define `BUILD`", SCRIPT_VERSION),
+                "Add `#define BUILD 0` to use a build number",
+            ));
+        }
+        Ok(Self::regex()
+            .replace_all(content, |c: &Captures| {
+                let value = match &c[2] {
+                    "MAJOR" => self.major,
+                    "MINOR" => self.minor,
+                    "BUILD" => self.build.unwrap_or(0),
+                    _ => self.patch,
+                };
+                format!("{}{}", &c[1], value)
+            })
+            .to_string())
+    }
+}
+
+impl std::fmt::Display for ScriptVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.build {
+            Some(build) => write!(f, "{}.{}.{}.{}", self.major, self.minor, self.patch, build),
+            None => write!(f, "{}.{}.{}", self.major, self.minor, self.patch),
+        }
+    }
+}
+
+/// Writes the version, committing and tagging it if requested
+fn write(version: &ScriptVersion, args: &clap::ArgMatches) -> Result<(), HEMTTError> {
+    let commit = args.is_present("commit") || args.is_present("tag");
+    let repo = match Repository::open(".") {
+        Ok(repo) => Some(repo),
+        Err(e) if commit => return Err(e.into()),
+        Err(_) => None,
+    };
+    if let Some(repo) = &repo {
+        if !args.is_present("force") && is_dirty(repo)? {
+            return Err(HEMTTError::generic(
+                "The working tree has uncommitted changes",
+                "Commit them first or use `--force`",
+            ));
+        }
+    }
+
+    version.write()?;
+    println!("{} {}", "Version".green().bold(), version);
+
+    if let (Some(repo), true) = (&repo, commit) {
+        let parent = repo.head()?.peel_to_commit()?;
+        let tree = version_tree(repo, &parent)?;
+        // Staged as well, otherwise the index would still hold the previous version
+        let mut index = repo.index()?;
+        index.add_path(Path::new(SCRIPT_VERSION))?;
+        index.write()?;
+        let signature = repo.signature()?;
+        let message = format!("Version {}", version);
+        let id = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &[&parent])?;
+        println!("{} {}", "Committed".green().bold(), message);
+        if args.is_present("tag") {
+            let name = format!("v{}", version);
+            repo.tag(&name, &repo.find_object(id, None)?, &signature, &message, false)?;
+            println!("{} {}", "Tagged".green().bold(), name);
+        }
+    }
+    Ok(())
+}
+
+/// The tree of `parent` with only `script_version.hpp` changed, other staged changes are not committed
+fn version_tree<'a>(repo: &'a Repository, parent: &Commit) -> Result<Tree<'a>, HEMTTError> {
+    let mut index = Index::new()?;
+    index.read_tree(&parent.tree()?)?;
+    let mut entry = index.get_path(Path::new(SCRIPT_VERSION), 0).ok_or_else(|| {
+        HEMTTError::generic(
+            format!("`{}` is not committed", SCRIPT_VERSION),
+            "Commit it before using `--commit` or `--tag`",
+        )
+    })?;
+    entry.id = repo.blob_path(Path::new(SCRIPT_VERSION))?;
+    entry.file_size = std::fs::metadata(SCRIPT_VERSION)?.len() as u32;
+    index.add(&entry)?;
+    Ok(repo.find_tree(index.write_tree_to(repo)?)?)
+}
+
+/// Are there changes to tracked files
+fn is_dirty(repo: &Repository) -> Result<bool, HEMTTError> {
+    let statuses = repo.statuses(Some(StatusOptions::new().include_untracked(false)))?;
+    Ok(!statuses.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENT: &str =
+        "#define MAJOR 1\n#define MINOR 2\n#define PATCHLVL 3\n#define BUILD 4\n\n#define VERSION MAJOR.MINOR\n";
+
+    fn version(major: u32, minor: u32, patch: u32, build: Option<u32>) -> ScriptVersion {
+        ScriptVersion {
+            major,
+            minor,
+            patch,
+            build,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(version(1, 2, 3, Some(4)), ScriptVersion::parse(CONTENT).unwrap());
+        assert_eq!(
+            version(1, 2, 3, None),
+            ScriptVersion::parse("#define MAJOR 1\n#define MINOR 2\n#define PATCH 3\n").unwrap()
+        );
+    }
+
+    #[test]
+    fn bump() {
+        let bumped = |part: &str, build: Option<u32>| {
+            let mut v = version(1, 2, 3, build);
+            v.bump(part);
+            v
+        };
+        assert_eq!(version(2, 0, 0, Some(0)), bumped("major", Some(4)));
+        assert_eq!(version(1, 3, 0, Some(0)), bumped("minor", Some(4)));
+        assert_eq!(version(1, 2, 4, Some(0)), bumped("patch", Some(4)));
+        assert_eq!(version(1, 2, 3, Some(5)), bumped("build", Some(4)));
+        assert_eq!(version(1, 2, 4, None), bumped("patch", None));
+        assert_eq!(version(1, 2, 3, Some(1)), bumped("build", None));
+    }
+
+    #[test]
+    fn set() {
+        let mut v = version(1, 2, 3, Some(4));
+        v.set("2.0.1").unwrap();
+        assert_eq!(version(2, 0, 1, Some(0)), v);
+        v.set("2.0.1.7").unwrap();
+        assert_eq!(version(2, 0, 1, Some(7)), v);
+        assert!(v.set("2.0").is_err());
+        assert!(v.set("2.0.1.7.8").is_err());
+        assert!(v.set("2.x.1").is_err());
+    }
+
+    #[test]
+    fn rewrite() {
+        assert_eq!(
+            "#define MAJOR 2\n#define MINOR 0\n#define PATCHLVL 1\n#define BUILD 0\n\n#define VERSION MAJOR.MINOR\n",
+            version(2, 0, 1, Some(0)).rewrite(CONTENT).unwrap()
+        );
+        assert!(version(1, 2, 3, Some(1))
+            .rewrite("#define MAJOR 1\n#define MINOR 2\n#define PATCH 3\n")
+            .is_err());
+    }
+
+    #[test]
+    fn sources() {
+        assert_eq!(None, source("", "", false));
+        assert!(source("1.0.0", "", false).is_some());
+        assert!(source("", "git", false).is_some());
+        assert!(source("", "", true).is_some());
+    }
+}
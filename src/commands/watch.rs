@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use colored::*;
+use walkdir::WalkDir;
+
+use crate::build::build::Build;
+use crate::cache::normalize;
+use crate::flow::events::Silent;
+use crate::{Addon, Command, Flow, HEMTTError, Project, Stage, Step};
+
+static POLL: u64 = 250;
+
+/// Modified time and size of every watched file
+type Snapshot = HashMap<String, (Option<SystemTime>, u64)>;
+
+pub struct Watch {}
+impl Command for Watch {
+    fn register(&self) -> clap::App {
+        crate::build::addon_args(
+            clap::SubCommand::with_name("watch")
+                .version(*crate::VERSION)
+                .about("Rebuild addons as their files change")
+                .arg(
+                    clap::Arg::with_name("debounce")
+                        .help("Milliseconds without changes to wait before rebuilding")
+                        .long("debounce")
+                        .takes_value(true)
+                        .default_value("500"),
+                ),
+        )
+    }
+
+    fn run(&self, args: &clap::ArgMatches, mut p: Project) -> Result<(), HEMTTError> {
+        let debounce = Duration::from_millis(args.value_of("debounce").unwrap().parse()?);
+        let addons = crate::build::get_selected_addons(args)?;
+        let build = Build::new(true, false);
+
+        // Snapshots are taken before building, files saved during a build are built in the next cycle
+        let mut previous = snapshot(&addons, &p)?;
+        if let Err(e) = cycle(addons.clone(), &build, &mut p) {
+            error!(e.to_string());
+        }
+        println!("{}", "Watching for changes".bold());
+
+        loop {
+            thread::sleep(Duration::from_millis(POLL));
+            let mut current = snapshot(&addons, &p)?;
+            if current == previous {
+                continue;
+            }
+            // Wait for a burst of saves to finish
+            loop {
+                thread::sleep(debounce);
+                let next = snapshot(&addons, &p)?;
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+
+            let changed = changes(&previous, &current);
+            previous = current;
+            let targets = match targets(&changed, &addons, &mut p) {
+                Ok(targets) => targets,
+                Err(e) => {
+                    error!(e.to_string());
+                    continue;
+                }
+            };
+            if !targets.is_empty() {
+                if let Err(e) = cycle(targets, &build, &mut p) {
+                    error!(e.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Addons to rebuild for the changed files, reloading the project if its files changed
+fn targets(changed: &[String], addons: &[Addon], p: &mut Project) -> Result<Vec<Addon>, HEMTTError> {
+    let version = p.version()?;
+    if changed.iter().any(|path| is_project_file(path)) {
+        *p = Project::read()?;
+    }
+    p.refresh_version();
+    Ok(if p.version()? != version {
+        addons.to_vec()
+    } else {
+        affected(changed, addons)
+    })
+}
+
+/// Builds the addons and prints a compact summary
+fn cycle(addons: Vec<Addon>, build: &Build, p: &mut Project) -> Result<(), HEMTTError> {
+    let start = Instant::now();
+    let flow = Flow {
+        steps: vec![
+            Step::parallel(
+                "🔍",
+                "Checks",
+                Stage::Check,
+                vec![
+                    Box::new(crate::build::prebuild::render::Render {}),
+                    Box::new(crate::build::checks::names::NotEmpty {}),
+                    Box::new(crate::build::checks::names::ValidName {}),
+                    Box::new(crate::build::checks::cache::Cache { use_bin: true }),
+                ],
+            ),
+            Step::parallel(
+                "🚧",
+                "Prebuild",
+                Stage::PreBuild,
+                vec![Box::new(crate::build::prebuild::preprocess::Preprocess {})],
+            ),
+            Step::parallel("📝", "Build", Stage::Build, vec![Box::new(build.clone())]),
+            Step::single(
+                "💾",
+                "",
+                Stage::Build,
+                vec![Box::new(crate::build::postbuild::cache::SaveCache {})],
+            ),
+        ],
+        keep_going: true,
+        dry_run: false,
+        time: false,
+        events: Arc::new(Silent {}),
+    };
+    let (addons, result) = flow.execute(addons, p);
+    // Clean up before returning so a failed cycle doesn't leave rendered files in the addons
+    crate::RENDERED.lock().unwrap().clean();
+    crate::CACHED.lock().unwrap().clear();
+
+    let mut built = 0;
+    for data in addons {
+        match data {
+            Ok((report, addon)) => {
+                report.print();
+                if report.stop.is_none() {
+                    built += 1;
+                    println!(
+                        "{} {} - {}",
+                        "Built".green().bold(),
+                        addon.name,
+                        report.rebuild.unwrap_or_default()
+                    );
+                }
+            }
+            Err(e) => error!(e.to_string()),
+        }
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} {} addons in {}.{:03} seconds",
+        "Finished".bold(),
+        built,
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    );
+    result
+}
+
+fn snapshot(addons: &[Addon], p: &Project) -> Result<Snapshot, HEMTTError> {
+    let mut snapshot = Snapshot::new();
+    let folders: Vec<PathBuf> = addons.iter().map(Addon::folder).collect();
+    for root in folders.iter().chain(p.include.iter()) {
+        if !root.exists() {
+            continue;
+        }
+        for entry in WalkDir::new(root) {
+            let entry = entry.map_err(std::io::Error::from)?;
+            if entry.path().is_dir() {
+                continue;
+            }
+            insert(&mut snapshot, entry.path());
+        }
+    }
+    for file in &["hemtt.toml", "hemtt.json"] {
+        insert(&mut snapshot, Path::new(file));
+    }
+    if Path::new(".hemtt").exists() {
+        for entry in std::fs::read_dir(".hemtt")? {
+            let path = entry?.path();
+            if path.is_file() {
+                insert(&mut snapshot, &path);
+            }
+        }
+    }
+    Ok(snapshot)
+}
+
+fn insert(snapshot: &mut Snapshot, path: &Path) {
+    if let Ok(metadata) = std::fs::metadata(path) {
+        snapshot.insert(normalize(path), (metadata.modified().ok(), metadata.len()));
+    }
+}
+
+/// Paths that were added, removed or modified
+fn changes(previous: &Snapshot, current: &Snapshot) -> Vec<String> {
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, data)| previous.get(path.as_str()) != Some(*data))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(previous.keys().filter(|path| !current.contains_key(*path)).cloned());
+    changed
+}
+
+fn is_project_file(path: &str) -> bool {
+    path == "hemtt.toml" || path == "hemtt.json" || (path.starts_with(".hemtt/") && !path.starts_with(".hemtt/cache/"))
+}
+
+/// Addons containing or including any of the changed files, or all addons for other changes
+fn affected(changed: &[String], addons: &[Addon]) -> Vec<Addon> {
+    let cache = crate::BUILD_CACHE.lock().unwrap();
+    let uses = |addon: &Addon, path: &str| {
+        path.starts_with(&format!("{}/", normalize(&addon.folder())))
+            || cache
+                .get(addon)
+                .map(|fingerprint| fingerprint.includes.contains_key(path))
+                .unwrap_or(false)
+    };
+    let unknown = changed
+        .iter()
+        .any(|path| !is_project_file(path) && !addons.iter().any(|addon| uses(addon, path.as_str())));
+    if unknown || changed.iter().any(|path| is_project_file(path)) {
+        return addons.to_vec();
+    }
+    addons
+        .iter()
+        .filter(|addon| changed.iter().any(|path| uses(addon, path.as_str())))
+        .cloned()
+        .collect()
+}
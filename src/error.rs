@@ -1,19 +1,6 @@
 use armake2::ArmakeError;
 use colored::*;
 
-pub trait PrintableError<T, E> {
-    fn unwrap_or_print(self) -> T;
-}
-impl<T, E: std::fmt::Debug + std::fmt::Display> PrintableError<T, E> for Result<T, E> {
-    fn unwrap_or_print(self) -> T {
-        if let Err(error) = &self {
-            error!(format!("{}", error));
-            std::process::exit(1);
-        }
-        self.unwrap()
-    }
-}
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct FileErrorLineNumber {
     pub file: String,
@@ -30,12 +17,37 @@ pub struct IOPathError {
     pub path: std::path::PathBuf,
 }
 
+/// A build script that could not be run
+#[derive(Debug)]
+pub enum ScriptError {
+    /// A shell command exited unsuccessfully
+    Failed(String),
+    /// The script is not defined in the project
+    Missing(String),
+}
+impl ScriptError {
+    pub fn message(&self) -> String {
+        match self {
+            ScriptError::Failed(_) => "Failed to execute shell command".to_owned(),
+            ScriptError::Missing(name) => format!("Script `{}` does not exist", name),
+        }
+    }
+
+    pub fn info(&self) -> String {
+        match self {
+            ScriptError::Failed(cmd) => cmd.clone(),
+            ScriptError::Missing(_) => "Scripts are defined in the project file".to_owned(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum HEMTTError {
     GENERIC(String, String),
     IO(std::io::Error),
     LINENO(FileErrorLineNumber),
     PATH(IOPathError),
+    SCRIPT(ScriptError),
     SIMPLE(String),
     TOML(toml::ser::Error),
 }
@@ -79,6 +91,7 @@ impl std::fmt::Display for HEMTTError {
             HEMTTError::IO(ref err) => write!(f, "IO error: {}", err),
             HEMTTError::LINENO(ref err) => write!(f, "{}\n{}", err.error, filepointer!(err)),
             HEMTTError::PATH(ref err) => write!(f, "IO error {}: {}", err.path.display(), err.source),
+            HEMTTError::SCRIPT(ref err) => write!(f, "{}\n    {}", err.message().bold(), err.info()),
             HEMTTError::SIMPLE(ref s) => write!(f, "{}", s),
             HEMTTError::TOML(ref err) => write!(f, "TOML error: {}", err),
         }
@@ -92,6 +105,7 @@ impl std::error::Error for HEMTTError {
             HEMTTError::IO(ref err) => Some(err),
             HEMTTError::LINENO(ref _e) => Some(self),
             HEMTTError::PATH(ref _err) => Some(self),
+            HEMTTError::SCRIPT(ref _err) => Some(self),
             HEMTTError::SIMPLE(ref _s) => Some(self),
             HEMTTError::TOML(ref err) => Some(err),
         }
@@ -122,6 +136,12 @@ impl From<toml::ser::Error> for HEMTTError {
     }
 }
 
+impl From<serde_json::Error> for HEMTTError {
+    fn from(err: serde_json::Error) -> HEMTTError {
+        HEMTTError::GENERIC("JSON error".to_owned(), err.to_string())
+    }
+}
+
 impl From<config::ConfigError> for HEMTTError {
     fn from(err: config::ConfigError) -> HEMTTError {
         let s = "Unable to open project config".to_string();
@@ -171,7 +191,7 @@ impl From<handlebars::TemplateRenderError> for HEMTTError {
                     HEMTTError::GENERIC("Render error".to_string(), e.reason.to_string())
                 }
             }
-            _ => unimplemented!(),
+            e => HEMTTError::GENERIC("Render error".to_string(), e.to_string()),
         }
     }
 }
@@ -245,3 +265,9 @@ impl From<zip::result::ZipError> for HEMTTError {
         }
     }
 }
+
+impl From<git2::Error> for HEMTTError {
+    fn from(err: git2::Error) -> HEMTTError {
+        HEMTTError::GENERIC("Git Error".to_owned(), err.message().to_owned())
+    }
+}
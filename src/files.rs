@@ -106,6 +106,11 @@ impl FileCache {
         Ok(())
     }
 
+    /// Forgets all cached files so they are read from disk again
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
     pub fn get_line(&mut self, path: &str, line: usize) -> Result<String, HEMTTError> {
         Ok(self.lines(path)?[line - 1].clone())
     }
@@ -151,9 +156,14 @@ impl RenderedFiles {
 impl RenderedFiles {
     pub fn clean(&mut self) {
         for (_, tmp) in self.redirects.iter() {
+            // Nothing is written during a dry run
+            if !Path::new(tmp).exists() {
+                continue;
+            }
             if let Err(e) = remove_file!(tmp) {
                 error!(e.to_string());
             }
         }
+        self.redirects.clear();
     }
 }
@@ -0,0 +1,146 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use super::BuildEvents;
+use crate::{Addon, Diagnostic, HEMTTError, Report, Severity, Stage, Step};
+
+/// A flow event sent by `Channel`, addons are identified by their folder
+#[derive(Clone, Debug)]
+pub enum Event {
+    StepStarted {
+        name: String,
+        stage: Stage,
+        addons: Vec<String>,
+    },
+    AddonStarted {
+        addon: String,
+    },
+    FileProcessed {
+        addon: String,
+        action: String,
+        file: String,
+    },
+    Warning {
+        addon: String,
+        warning: Diagnostic,
+    },
+    AddonFinished {
+        addon: String,
+        failed: bool,
+    },
+    StepFinished {
+        name: String,
+        stage: Stage,
+    },
+    AddonFailed {
+        addon: String,
+        error: Diagnostic,
+    },
+    AddonSkipped {
+        addon: String,
+        reason: Diagnostic,
+    },
+    ReleaseSkipped,
+    Error {
+        error: Diagnostic,
+    },
+    Summary {
+        failures: Vec<String>,
+    },
+}
+
+/// Sends every event to a receiver, for library users and interfaces running HEMTT in another thread
+pub struct Channel {
+    tx: Mutex<Sender<Event>>,
+}
+impl Channel {
+    pub fn new() -> (Self, Receiver<Event>) {
+        let (tx, rx) = mpsc::channel();
+        (Self { tx: Mutex::new(tx) }, rx)
+    }
+
+    fn send(&self, event: Event) {
+        // The receiver may have been dropped, which only means nobody is listening
+        let _ = self.tx.lock().unwrap().send(event);
+    }
+}
+
+impl BuildEvents for Channel {
+    fn step_started(&self, step: &Step, addons: &[&Addon]) {
+        self.send(Event::StepStarted {
+            name: step.name.clone(),
+            stage: step.stage.clone(),
+            addons: addons.iter().map(|&addon| key(addon)).collect(),
+        });
+    }
+
+    fn addon_started(&self, _: &Step, addon: &Addon) {
+        self.send(Event::AddonStarted { addon: key(addon) });
+    }
+
+    fn file_processed(&self, addon: &Addon, action: &str, file: &str) {
+        self.send(Event::FileProcessed {
+            addon: key(addon),
+            action: action.to_owned(),
+            file: file.to_owned(),
+        });
+    }
+
+    fn warning(&self, addon: &Addon, warning: &Diagnostic) {
+        self.send(Event::Warning {
+            addon: key(addon),
+            warning: warning.clone(),
+        });
+    }
+
+    fn addon_finished(&self, _: &Step, addon: &Addon, report: &Report) {
+        self.send(Event::AddonFinished {
+            addon: key(addon),
+            failed: match report.stop {
+                Some((fatal, _)) => fatal,
+                None => false,
+            },
+        });
+    }
+
+    fn step_finished(&self, step: &Step) {
+        self.send(Event::StepFinished {
+            name: step.name.clone(),
+            stage: step.stage.clone(),
+        });
+    }
+
+    fn addon_failed(&self, addon: &Addon, error: &Diagnostic) {
+        self.send(Event::AddonFailed {
+            addon: key(addon),
+            error: error.clone(),
+        });
+    }
+
+    fn addon_skipped(&self, addon: &Addon, reason: &Diagnostic) {
+        self.send(Event::AddonSkipped {
+            addon: key(addon),
+            reason: reason.clone(),
+        });
+    }
+
+    fn release_skipped(&self) {
+        self.send(Event::ReleaseSkipped);
+    }
+
+    fn error(&self, error: &HEMTTError) {
+        self.send(Event::Error {
+            error: Diagnostic::new(Severity::Error, error, &Stage::None, ""),
+        });
+    }
+
+    fn summary(&self, failures: &[String]) {
+        self.send(Event::Summary {
+            failures: failures.to_vec(),
+        });
+    }
+}
+
+fn key(addon: &Addon) -> String {
+    addon.folder().display().to_string()
+}
@@ -0,0 +1,58 @@
+use colored::*;
+
+use super::BuildEvents;
+use crate::{Addon, Diagnostic, HEMTTError, Report, Step};
+
+/// A line for each step and addon, for CI logs and terminals without progress bars
+pub struct LineLog {}
+impl BuildEvents for LineLog {
+    fn step_started(&self, step: &Step, _: &[&Addon]) {
+        if step.name.is_empty() {
+            return;
+        }
+        if !cfg!(windows) {
+            println!("{} {}", step.emoji, &fill_space!(" ", 12, &step.name).bold().cyan());
+        } else {
+            println!("{}", &fill_space!(" ", 12, &step.name).bold().cyan());
+        }
+    }
+
+    fn file_processed(&self, addon: &Addon, action: &str, file: &str) {
+        debug!("{} {} - {}", addon.name, action, file);
+    }
+
+    fn addon_finished(&self, step: &Step, addon: &Addon, report: &Report) {
+        if step.name.is_empty() || report.stop.is_some() {
+            return;
+        }
+        println!("    {}", addon.name);
+    }
+
+    fn addon_failed(&self, addon: &Addon, _: &Diagnostic) {
+        super::addon_failed(addon);
+    }
+
+    fn addon_skipped(&self, _: &Addon, reason: &Diagnostic) {
+        super::addon_skipped(reason);
+    }
+
+    fn release_skipped(&self) {
+        super::release_skipped();
+    }
+
+    fn report(&self, _: &Addon, report: &Report) {
+        report.print();
+    }
+
+    fn error(&self, error: &HEMTTError) {
+        error!(format!("{}", error));
+    }
+
+    fn slowest(&self, addons: &[(&Report, &Addon)]) {
+        super::slowest(addons);
+    }
+
+    fn summary(&self, failures: &[String]) {
+        super::summary(failures);
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+mod channel;
+mod log;
+mod terminal;
+
+pub use channel::{Channel, Event};
+pub use log::LineLog;
+pub use terminal::Terminal;
+
+use crate::{Addon, Diagnostic, HEMTTError, Report, Stage, Step};
+
+/// Number of addons listed by `--time`
+static SLOWEST: usize = 5;
+
+/// Receives the progress of a flow, every method does nothing by default
+pub trait BuildEvents: std::marker::Send + std::marker::Sync {
+    /// A step is starting, `addons` are the addons that will run its tasks
+    fn step_started(&self, _step: &Step, _addons: &[&Addon]) {}
+    /// An addon is starting the tasks of a step
+    fn addon_started(&self, _step: &Step, _addon: &Addon) {}
+    /// A task is working on a file of the addon
+    fn file_processed(&self, _addon: &Addon, _action: &str, _file: &str) {}
+    /// A task reported a warning for the addon
+    fn warning(&self, _addon: &Addon, _warning: &Diagnostic) {}
+    /// An addon has finished the tasks of a step
+    fn addon_finished(&self, _step: &Step, _addon: &Addon, _report: &Report) {}
+    /// Every addon has finished the step
+    fn step_finished(&self, _step: &Step) {}
+    /// An addon failed and will not be built
+    fn addon_failed(&self, _addon: &Addon, _error: &Diagnostic) {}
+    /// An addon is skipped because an addon it depends on was not built
+    fn addon_skipped(&self, _addon: &Addon, _reason: &Diagnostic) {}
+    /// The release steps are skipped because not every addon was built
+    fn release_skipped(&self) {}
+    /// The warnings and errors of an addon that have not been shown yet
+    fn report(&self, _addon: &Addon, _report: &Report) {}
+    /// An error that is not tied to an addon
+    fn error(&self, _error: &HEMTTError) {}
+    /// The addons that took the longest, when timings are requested
+    fn slowest(&self, _addons: &[(&Report, &Addon)]) {}
+    /// The flow has finished, `failures` describes every addon that failed
+    fn summary(&self, _failures: &[String]) {}
+}
+
+/// Ignores every event, used by commands that print their own summary and reports
+pub struct Silent {}
+impl BuildEvents for Silent {}
+
+/// Progress bars on a terminal, or plain lines when progress bars are disabled or for a dry run
+pub fn default_events(dry_run: bool) -> Arc<dyn BuildEvents> {
+    if *crate::NOPB || dry_run {
+        Arc::new(LineLog {})
+    } else {
+        Arc::new(Terminal::new())
+    }
+}
+
+/// Output shared by `Terminal` and `LineLog` for the events that print the same either way
+fn addon_failed(addon: &Addon) {
+    println!();
+    error!(&format!("Unable to build `{}`", addon.folder().display()));
+}
+
+fn addon_skipped(reason: &Diagnostic) {
+    println!();
+    warnmessage!(&reason.message, reason.info.as_ref().map(String::as_str).unwrap_or_default());
+}
+
+fn release_skipped() {
+    println!();
+    warn!("Skipping the release, not every addon was built");
+}
+
+fn summary(failures: &[String]) {
+    if failures.is_empty() {
+        return;
+    }
+    println!();
+    error!("Failed to build {} addon(s)", failures.len());
+    for failure in failures {
+        println!("    {}", failure);
+    }
+}
+
+/// Prints the addons that took the longest along with the time of each stage
+fn slowest(addons: &[(&Report, &Addon)]) {
+    if addons.is_empty() {
+        return;
+    }
+    println!();
+    println!("Slowest addons");
+    for (report, addon) in addons.iter().take(SLOWEST) {
+        let mut stages: Vec<Stage> = Vec::new();
+        for timing in &report.timings {
+            if !stages.contains(&timing.stage) {
+                stages.push(timing.stage.clone());
+            }
+        }
+        let stages: Vec<String> = stages
+            .iter()
+            .map(|stage| format!("{} {}", stage, seconds(report.stage_time(stage))))
+            .collect();
+        println!(
+            "    {} {} ({})",
+            fill_space!(" ", 20, &addon.name),
+            seconds(report.time),
+            stages.join(", ")
+        );
+    }
+}
+
+fn seconds(time: Duration) -> String {
+    format!("{}.{:03}s", time.as_secs(), time.subsec_millis())
+}
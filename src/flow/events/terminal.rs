@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use colored::*;
+
+#[cfg(not(windows))]
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+#[cfg(windows)]
+use indicatif_windows::{MultiProgress, ProgressBar, ProgressStyle};
+
+use super::BuildEvents;
+use crate::{Addon, Diagnostic, HEMTTError, Report, Step};
+
+static CMD_GAP: usize = 18;
+
+/// A progress bar for each step and for each addon in the step
+pub struct Terminal {
+    progress: Mutex<Option<Progress>>,
+}
+impl Terminal {
+    pub fn new() -> Self {
+        Self {
+            progress: Mutex::new(None),
+        }
+    }
+}
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Progress {
+    total: ProgressBar,
+    addons: HashMap<PathBuf, ProgressBar>,
+    done: Arc<AtomicBool>,
+    draw: JoinHandle<()>,
+    ticker: JoinHandle<()>,
+}
+
+impl BuildEvents for Terminal {
+    fn step_started(&self, step: &Step, addons: &[&Addon]) {
+        if !step.parallel {
+            if !step.name.is_empty() {
+                if !cfg!(windows) {
+                    println!("{} {}", step.emoji, &fill_space!(" ", 12, &step.name).bold().cyan());
+                } else {
+                    println!("{}", &fill_space!(" ", 12, &step.name).bold().cyan());
+                }
+            }
+            return;
+        }
+
+        let addon_style = ProgressStyle::default_spinner()
+            .tick_chars("\\|/| ")
+            .template("{prefix:.bold.dim} {spinner} {wide_msg}");
+        let master_style = ProgressStyle::default_bar()
+            .template("{prefix:.bold.cyan/blue} {spinner:.yellow} [{elapsed_precise}] [{bar:30.cyan/blue}] [{pos}|{len}]")
+            .progress_chars("#>-");
+
+        let m = MultiProgress::new();
+        let total = m.add(ProgressBar::new(addons.len() as u64));
+        total.set_style(master_style);
+        if !step.name.is_empty() {
+            if !cfg!(windows) {
+                total.set_prefix(&format!("{} {}", step.emoji, &fill_space!(" ", 12, &step.name)));
+            } else {
+                total.set_prefix(&fill_space!(" ", 12, &step.name).to_string());
+            }
+        }
+
+        let mut bars = HashMap::new();
+        for addon in addons {
+            let pb = m.add(ProgressBar::new(0));
+            pb.set_style(addon_style.clone());
+            pb.set_prefix(&fill_space!(" ", 16, &addon.name));
+            bars.insert(addon.folder(), pb);
+        }
+
+        let draw = thread::spawn(move || {
+            m.join().unwrap();
+        });
+
+        // tick the top bar every 100 ms to keep the multiprogress updated
+        let done = Arc::new(AtomicBool::new(false));
+        let ticker = {
+            let done = done.clone();
+            let total = total.clone();
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(100));
+                    total.tick();
+                }
+            })
+        };
+
+        *self.progress.lock().unwrap() = Some(Progress {
+            total,
+            addons: bars,
+            done,
+            draw,
+            ticker,
+        });
+    }
+
+    fn addon_started(&self, _: &Step, addon: &Addon) {
+        if let Some(progress) = self.progress.lock().unwrap().as_ref() {
+            if let Some(pb) = progress.addons.get(&addon.folder()) {
+                pb.tick();
+            }
+        }
+    }
+
+    fn file_processed(&self, addon: &Addon, action: &str, file: &str) {
+        if let Some(progress) = self.progress.lock().unwrap().as_ref() {
+            if let Some(pb) = progress.addons.get(&addon.folder()) {
+                pb.set_message(&format!("{} - {}", &fill_space!(" ", CMD_GAP, action), file));
+            }
+        }
+    }
+
+    fn addon_finished(&self, _: &Step, addon: &Addon, _: &Report) {
+        if let Some(progress) = self.progress.lock().unwrap().as_ref() {
+            if let Some(pb) = progress.addons.get(&addon.folder()) {
+                pb.finish_and_clear();
+                progress.total.inc(1);
+            }
+        }
+    }
+
+    fn step_finished(&self, _: &Step) {
+        let progress = self.progress.lock().unwrap().take();
+        if let Some(progress) = progress {
+            progress.done.store(true, Ordering::Relaxed);
+            progress.ticker.join().unwrap();
+            for pb in progress.addons.values() {
+                pb.finish_and_clear();
+            }
+            progress.total.finish();
+            progress.draw.join().unwrap();
+        }
+    }
+
+    fn addon_failed(&self, addon: &Addon, _: &Diagnostic) {
+        super::addon_failed(addon);
+    }
+
+    fn addon_skipped(&self, _: &Addon, reason: &Diagnostic) {
+        super::addon_skipped(reason);
+    }
+
+    fn release_skipped(&self) {
+        super::release_skipped();
+    }
+
+    fn report(&self, _: &Addon, report: &Report) {
+        report.print();
+    }
+
+    fn error(&self, error: &HEMTTError) {
+        error!(format!("{}", error));
+    }
+
+    fn slowest(&self, addons: &[(&Report, &Addon)]) {
+        super::slowest(addons);
+    }
+
+    fn summary(&self, failures: &[String]) {
+        super::summary(failures);
+    }
+}
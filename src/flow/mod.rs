@@ -1,40 +1,55 @@
-use std::sync::mpsc::{self, TryRecvError};
-use std::thread;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use colored::*;
 use rayon::prelude::*;
 
-#[cfg(not(windows))]
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-#[cfg(windows)]
-use indicatif_windows::{MultiProgress, ProgressBar, ProgressStyle};
-
+pub mod events;
 mod report;
 mod script;
 mod stage;
 mod step;
 mod task;
 
-pub use report::Report;
+pub use events::{default_events, BuildEvents};
+pub use report::{millis, Diagnostic, Report, Severity, Timing};
 pub use script::BuildScript;
 pub use script::Script;
 pub use stage::Stage;
 pub use step::Step;
 pub use task::Task;
 
+use crate::cache::normalize;
 use crate::{Addon, AddonList, HEMTTError, Project};
 
 #[derive(Clone)]
 pub struct Flow {
     pub steps: Vec<Step>,
+    /// Continue with the remaining addons when an addon fails
+    pub keep_going: bool,
+    /// Report what the tasks would change without changing it
+    pub dry_run: bool,
+    /// List the slowest addons once the flow finishes
+    pub time: bool,
+    /// Receives the progress of the steps
+    pub events: Arc<dyn BuildEvents>,
 }
 
 impl Flow {
-    /// Execute the flow against a vector of addons
-    pub fn execute(&self, addons: Vec<Addon>, p: &mut Project) -> AddonList {
+    /// Execute the flow against a vector of addons, returning the addons along with the error that stopped the flow
+    ///
+    /// The addons are returned even when a step fails, so the reports of a failed build can still be written
+    pub fn execute(
+        &self,
+        addons: Vec<Addon>,
+        p: &mut Project,
+    ) -> (Vec<Result<(Report, Addon), HEMTTError>>, Result<(), HEMTTError>) {
         let mut addons: Vec<Result<(Report, Addon), HEMTTError>> =
             addons.into_iter().map(|addon| Ok((Report::new(), addon))).collect();
+        let mut failed: Vec<String> = Vec::new();
+        let mut skipped: Vec<String> = Vec::new();
+        let mut skipped_release = false;
 
         for step in &self.steps {
             if step.none {
@@ -43,11 +58,27 @@ impl Flow {
             if addons.is_empty() {
                 continue;
             }
-            if step.parallel {
-                addons = self.parallel(&step.emoji, &step, addons, p)?;
-            } else {
-                addons = self.single(&step.emoji, &step, addons, p)?;
+            // A release depends on every addon, the other steps skip stopped addons on their own
+            if step.stage == Stage::ReleaseBuild && addons.iter().any(is_failure) {
+                if !skipped_release {
+                    skipped_release = true;
+                    self.events.release_skipped();
+                }
+                continue;
             }
+            let span = crate::trace::span(step_name(step), "step").arg("stage", step.stage.to_string());
+            // Steps consume the addons, a copy is kept in case the step fails
+            let copy = addons.iter().map(copy).collect();
+            let result = if step.parallel {
+                self.parallel(step, addons, p)
+            } else {
+                self.single(step, addons, p)
+            };
+            drop(span);
+            addons = match result {
+                Ok(addons) => addons,
+                Err(e) => return (copy, Err(e)),
+            };
 
             // Check for stopped reports
             let mut can_continue = true;
@@ -56,163 +87,133 @@ impl Flow {
                     can_continue = false;
                 } else {
                     let (report, addon) = d.as_ref().unwrap();
-                    if let Some((fatal, _)) = report.stop {
-                        if fatal {
+                    if let Some((true, error)) = &report.stop {
+                        let folder = addon.folder().display().to_string();
+                        if !failed.contains(&folder) {
                             can_continue = false;
-                            println!();
-                            error!(&format!("Unable to build `{}`", addon.folder().display().to_string()))
+                            self.events
+                                .addon_failed(addon, &Diagnostic::new(Severity::Error, error, &step.stage, ""));
+                            failed.push(folder);
                         }
                     }
                 }
             });
 
-            if !can_continue {
-                std::process::exit(1);
+            if !can_continue && !self.keep_going {
+                break;
+            }
+            if self.keep_going {
+                self.skip_dependents(&mut addons, &mut skipped);
             }
         }
 
         for data in &mut addons {
             match data {
-                Ok((report, _)) => {
-                    report.display();
-                }
-                Err(e) => {
-                    error!(format!("{}", e));
+                Ok((report, addon)) => {
+                    self.events.report(addon, report);
+                    report.printed();
                 }
+                Err(e) => self.events.error(e),
             }
         }
-        Ok(addons)
+        if self.time {
+            let mut timed: Vec<(&Report, &Addon)> = addons
+                .iter()
+                .filter_map(|data| data.as_ref().ok())
+                .filter(|(report, _)| report.time > Duration::new(0, 0))
+                .map(|(report, addon)| (report, addon))
+                .collect();
+            timed.sort_by(|(a, _), (b, _)| b.time.cmp(&a.time));
+            self.events.slowest(&timed);
+        }
+        (addons, Ok(()))
     }
 
-    pub fn parallel(
-        &self,
-        emoji: &str,
-        step: &Step,
-        addons: Vec<Result<(Report, Addon), HEMTTError>>,
-        p: &mut Project,
-    ) -> AddonList {
-        let addon_style = ProgressStyle::default_spinner()
-            .tick_chars("\\|/| ")
-            .template("{prefix:.bold.dim} {spinner} {wide_msg}");
-        let master_style = ProgressStyle::default_bar()
-            .template("{prefix:.bold.cyan/blue} {spinner:.yellow} [{elapsed_precise}] [{bar:30.cyan/blue}] [{pos}|{len}]")
-            .progress_chars("#>-");
-
-        // Create a multiprogress bar
-        let m = MultiProgress::new();
-        // Create the top bar
-        let mut total = 0;
-        let total_pb = m.add(ProgressBar::new(0));
-        total_pb.set_style(master_style.clone());
-
-        if !step.name.is_empty() {
-            if !cfg!(windows) {
-                total_pb.set_prefix(&format!("{} {}", emoji, &fill_space!(" ", 12, &step.name)));
-            } else {
-                total_pb.set_prefix(&fill_space!(" ", 12, &step.name).to_string());
+    /// Lists every addon that failed, returning an error if there were any
+    pub fn summary(&self, addons: &[Result<(Report, Addon), HEMTTError>]) -> Result<(), HEMTTError> {
+        let mut failures = Vec::new();
+        for data in addons {
+            match data {
+                Ok((report, addon)) => {
+                    if let Some((true, error)) = &report.stop {
+                        failures.push(format!(
+                            "{} - {}",
+                            addon.folder().display(),
+                            Diagnostic::new(Severity::Error, error, &Stage::None, "").message
+                        ));
+                    }
+                }
+                Err(e) => failures.push(Diagnostic::new(Severity::Error, e, &Stage::None, "").message),
             }
         }
+        self.events.summary(&failures);
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(HEMTTError::simple(format!("Failed to build {} addon(s)", failures.len())))
+    }
 
-        // Create a progress bar for each addon
-        let addons: Vec<Result<(ProgressBar, Report, Addon), HEMTTError>> = addons
-            .into_iter()
-            .map(|data| {
-                let (report, addon) = data?;
-                if report.stop.is_none() {
-                    total += 1;
-                    total_pb.set_length(total);
-                }
-                Ok((m.add(ProgressBar::new(0)), report, addon))
+    pub fn parallel(&self, step: &Step, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &mut Project) -> AddonList {
+        let events = &*self.events;
+        let running: Vec<&Addon> = addons
+            .iter()
+            .filter_map(|data| match data {
+                Ok((report, addon)) if report.stop.is_none() => Some(addon),
+                _ => None,
             })
             .collect();
-
-        let draw_thread = if !*crate::NOPB {
-            thread::spawn(move || {
-                m.join().unwrap();
-            })
-        } else {
-            thread::spawn(|| {})
-        };
-
-        let (tx, rx) = mpsc::channel();
-
-        if !*crate::NOPB {
-            // tick the top bar every 100 ms to keep the multiprogress updated
-            thread::spawn(move || 'outer: loop {
-                thread::sleep(Duration::from_millis(100));
-                loop {
-                    match rx.try_recv() {
-                        Ok(v) => {
-                            if v == 0 {
-                                total_pb.finish();
-                                break 'outer;
-                            } else {
-                                total_pb.inc(v);
-                            }
-                        }
-                        Err(TryRecvError::Disconnected) => {
-                            break 'outer;
-                        }
-                        Err(TryRecvError::Empty) => {
-                            break;
-                        }
-                    }
-                    total_pb.tick();
-                }
-                total_pb.tick();
-            });
-        } else if !cfg!(windows) {
-            println!("{} {}", emoji, &fill_space!(" ", 12, &step.name).bold().cyan());
-        } else {
-            println!("{}", &fill_space!(" ", 12, &step.name).bold().cyan());
-        }
+        events.step_started(step, &running);
 
         // Task loop
         let addons: Vec<Result<(Report, Addon), HEMTTError>> = addons
             .into_par_iter()
-            .map_with(
-                tx.clone(),
-                |tx, data: Result<(ProgressBar, Report, Addon), HEMTTError>| -> Result<(Report, Addon), HEMTTError> {
-                    let (pb, mut report, addon) = data?;
-
-                    if !*crate::NOPB {
-                        pb.set_style(addon_style.clone());
-                        pb.set_prefix(&fill_space!(" ", 16, &addon.name));
-                    }
+            .map(|data| -> Result<(Report, Addon), HEMTTError> {
+                let (mut report, addon) = data?;
+                let _span = crate::trace::span(addon.name.clone(), "addon").arg("step", step_name(step));
 
-                    let add = report.stop.is_none();
+                let add = report.stop.is_none();
+                if add {
+                    events.addon_started(step, &addon);
+                }
 
-                    for task in &step.tasks {
-                        if report.stop.is_none() && task.can_run(&addon, &report, p, &step.stage)? {
-                            pb.tick();
-                            report.absorb(match task.parallel(&addon, &report, p, &step.stage, &pb) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    pb.finish_and_clear();
-                                    return Err(e);
-                                }
-                            });
+                for task in &step.tasks {
+                    if report.stop.is_none() && task.can_run(&addon, &report, p, &step.stage)? {
+                        let _span = crate::trace::span(task.name(), "task").arg("addon", addon.name.clone());
+                        let start = Instant::now();
+                        let mut task_report = match task.parallel(&addon, &report, p, &step.stage, events, self.dry_run) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                events.addon_finished(step, &addon, &report);
+                                return Err(e);
+                            }
+                        };
+                        task_report.tag(&step.stage, task.name());
+                        task_report.timed(&step.stage, task.name(), start.elapsed());
+                        for diagnostic in &task_report.diagnostics {
+                            if diagnostic.severity == Severity::Warning {
+                                events.warning(&addon, diagnostic);
+                            }
                         }
+                        report.absorb(task_report);
                     }
+                }
 
-                    pb.finish_and_clear();
-                    if add {
-                        tx.send(1).unwrap();
-                    }
-                    Ok((report, addon))
-                },
-            )
+                if add {
+                    events.addon_finished(step, &addon, &report);
+                }
+                Ok((report, addon))
+            })
             .collect();
 
-        tx.send(0).unwrap();
-        draw_thread.join().unwrap();
+        events.step_finished(step);
 
         let addons = addons
             .into_iter()
             .map(|data| {
                 if let Ok((mut report, addon)) = data {
                     if report.stop.is_some() {
-                        report.display();
+                        self.events.report(&addon, &report);
+                        report.printed();
                     }
                     Ok((report, addon))
                 } else {
@@ -224,33 +225,46 @@ impl Flow {
         Ok(addons)
     }
 
-    fn single(
-        &self,
-        emoji: &str,
-        step: &Step,
-        addons: Vec<Result<(Report, Addon), HEMTTError>>,
-        p: &mut Project,
-    ) -> AddonList {
-        if !step.name.is_empty() {
-            if !cfg!(windows) {
-                println!("{} {}", emoji, &fill_space!(" ", 12, &step.name).bold().cyan());
-            } else {
-                println!("{}", &fill_space!(" ", 12, &step.name).bold().cyan());
-            }
-        }
+    fn single(&self, step: &Step, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &mut Project) -> AddonList {
+        self.events.step_started(step, &[]);
 
         let mut addons = addons;
 
         for task in &step.tasks {
-            addons = task.single(addons, p, &step.stage)?;
+            let _span = crate::trace::span(task.name(), "task");
+            // Tasks return the whole reports, only the warnings after these counts are new
+            let seen: HashMap<PathBuf, usize> = addons
+                .iter()
+                .filter_map(|data| data.as_ref().ok())
+                .map(|(report, addon)| (addon.folder(), report.warnings.len()))
+                .collect();
+            addons = task.single(addons, p, &step.stage, self.dry_run)?;
+            for data in &mut addons {
+                if let Ok((report, addon)) = data {
+                    let seen = seen.get(&addon.folder()).cloned().unwrap_or(0);
+                    let warnings: Vec<Diagnostic> = report
+                        .warnings
+                        .iter()
+                        .skip(seen)
+                        .map(|warning| Diagnostic::new(Severity::Warning, warning, &step.stage, task.name()))
+                        .collect();
+                    for warning in warnings {
+                        self.events.warning(addon, &warning);
+                        report.diagnostics.push(warning);
+                    }
+                }
+            }
         }
 
+        self.events.step_finished(step);
+
         let addons = addons
             .into_iter()
             .map(|data| {
                 if let Ok((mut report, addon)) = data {
                     if report.stop.is_some() {
-                        report.display();
+                        self.events.report(&addon, &report);
+                        report.printed();
                     }
                     Ok((report, addon))
                 } else {
@@ -261,4 +275,78 @@ impl Flow {
 
         Ok(addons)
     }
+
+    /// Stops the addons that include files from a failed or skipped addon, `skipped` collects their folders
+    fn skip_dependents(&self, addons: &mut [Result<(Report, Addon), HEMTTError>], skipped: &mut Vec<String>) {
+        let cache = crate::BUILD_CACHE.lock().unwrap();
+        let mut stopped: Vec<String> = addons
+            .iter()
+            .filter(|data| is_failure(data))
+            .filter_map(|data| data.as_ref().ok())
+            .map(|(_, addon)| normalize(&addon.folder()))
+            .chain(skipped.iter().cloned())
+            .collect();
+        loop {
+            let mut changed = false;
+            for data in addons.iter_mut() {
+                if let Ok((report, addon)) = data {
+                    if report.stop.is_some() {
+                        continue;
+                    }
+                    let dependency = cache
+                        .latest(addon)
+                        .and_then(|fingerprint| fingerprint.depends_on(&stopped))
+                        .cloned();
+                    if let Some(dependency) = dependency {
+                        let folder = normalize(&addon.folder());
+                        let reason = HEMTTError::generic(
+                            format!("Skipped `{}`", folder),
+                            format!("It includes files from `{}`, which was not built", dependency),
+                        );
+                        self.events
+                            .addon_skipped(addon, &Diagnostic::new(Severity::Warning, &reason, &Stage::None, ""));
+                        report.stop = Some((false, reason));
+                        skipped.push(folder.clone());
+                        stopped.push(folder);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+fn copy(data: &Result<(Report, Addon), HEMTTError>) -> Result<(Report, Addon), HEMTTError> {
+    match data {
+        Ok((report, addon)) => Ok((report.copy(), addon.clone())),
+        Err(e) => Err(HEMTTError::simple(
+            Diagnostic::new(Severity::Error, e, &Stage::None, "").message,
+        )),
+    }
+}
+
+fn is_failure(data: &Result<(Report, Addon), HEMTTError>) -> bool {
+    match data {
+        Ok((report, _)) => match report.stop {
+            Some((fatal, _)) => fatal,
+            None => false,
+        },
+        Err(_) => true,
+    }
+}
+
+/// Label of a step in traces, steps without a name are labelled by their tasks
+fn step_name(step: &Step) -> String {
+    if !step.name.is_empty() {
+        return step.name.clone();
+    }
+    if step.tasks.iter().all(|task| task.name() == "script") {
+        format!("{} scripts", step.stage)
+    } else {
+        let tasks: Vec<&str> = step.tasks.iter().map(|task| task.name()).collect();
+        tasks.join(", ")
+    }
 }
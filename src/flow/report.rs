@@ -1,4 +1,83 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
 use crate::error::HEMTTError;
+use crate::Stage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A warning or error along with where it came from
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub info: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub note: Option<String>,
+    pub stage: String,
+    pub task: String,
+}
+impl Diagnostic {
+    pub fn new(severity: Severity, error: &HEMTTError, stage: &Stage, task: &str) -> Self {
+        let mut diagnostic = Self {
+            severity,
+            message: String::new(),
+            info: None,
+            file: None,
+            line: None,
+            column: None,
+            note: None,
+            stage: stage.to_string(),
+            task: task.to_string(),
+        };
+        match error {
+            HEMTTError::GENERIC(s, v) => {
+                diagnostic.message = s.clone();
+                diagnostic.info = Some(v.clone());
+            }
+            HEMTTError::LINENO(e) => {
+                diagnostic.message = e.error.clone();
+                diagnostic.file = Some(e.file.clone());
+                diagnostic.line = e.line;
+                diagnostic.column = e.col;
+                diagnostic.note = e.note.clone();
+            }
+            HEMTTError::PATH(e) => {
+                diagnostic.message = e.source.to_string();
+                diagnostic.file = Some(e.path.display().to_string());
+            }
+            HEMTTError::SCRIPT(e) => {
+                diagnostic.message = e.message();
+                diagnostic.info = Some(e.info());
+            }
+            HEMTTError::IO(e) => diagnostic.message = e.to_string(),
+            HEMTTError::SIMPLE(s) => diagnostic.message = s.clone(),
+            HEMTTError::TOML(e) => diagnostic.message = e.to_string(),
+        }
+        diagnostic
+    }
+}
+
+/// Whole milliseconds in a duration
+pub fn millis(time: Duration) -> u64 {
+    time.as_secs() * 1000 + u64::from(time.subsec_millis())
+}
+
+/// Time an addon spent in a task
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timing {
+    pub stage: Stage,
+    pub task: &'static str,
+    pub time: Duration,
+}
 
 #[derive(Debug, Default)]
 pub struct Report {
@@ -6,6 +85,14 @@ pub struct Report {
     pub warnings: Vec<HEMTTError>,
     pub old: Vec<HEMTTError>,
     pub stop: Option<(bool, HEMTTError)>,
+    /// Why the addon needs to be built
+    pub rebuild: Option<String>,
+    /// Every warning and error, tagged with the stage and task that raised it
+    pub diagnostics: Vec<Diagnostic>,
+    /// Time spent running tasks for the addon
+    pub time: Duration,
+    /// Time spent in each task
+    pub timings: Vec<Timing>,
     displayed_stop: bool,
 }
 
@@ -16,6 +103,10 @@ impl Report {
             old: Vec::new(),
             errors: Vec::new(),
             stop: None,
+            rebuild: None,
+            diagnostics: Vec::new(),
+            time: Duration::new(0, 0),
+            timings: Vec::new(),
             displayed_stop: false,
         }
     }
@@ -28,12 +119,79 @@ impl Report {
             self.stop = other.stop;
             self.displayed_stop = other.displayed_stop;
         };
+        if self.rebuild.is_none() {
+            self.rebuild = other.rebuild;
+        }
+        for diagnostic in other.diagnostics {
+            if !self.diagnostics.contains(&diagnostic) {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+        self.time += other.time;
+        self.timings.append(&mut other.timings);
         for error in other.errors {
             self.unique_error(error);
         }
     }
 
+    /// Copy of the parts used by `Flow::summary` and the written reports, errors are kept as their message
+    pub fn copy(&self) -> Self {
+        let mut report = Self::new();
+        report.stop = self.stop.as_ref().map(|(fatal, error)| {
+            (
+                *fatal,
+                HEMTTError::simple(Diagnostic::new(Severity::Error, error, &Stage::None, "").message),
+            )
+        });
+        report.rebuild = self.rebuild.clone();
+        report.diagnostics = self.diagnostics.clone();
+        report.time = self.time;
+        report.timings = self.timings.clone();
+        report.displayed_stop = self.displayed_stop;
+        report
+    }
+
+    /// Records the time a task took
+    pub fn timed(&mut self, stage: &Stage, task: &'static str, time: Duration) {
+        self.time += time;
+        self.timings.push(Timing {
+            stage: stage.clone(),
+            task,
+            time,
+        });
+    }
+
+    /// Time spent in the tasks of a stage
+    pub fn stage_time(&self, stage: &Stage) -> Duration {
+        self.timings
+            .iter()
+            .filter(|timing| &timing.stage == stage)
+            .map(|timing| timing.time)
+            .sum()
+    }
+
+    /// Records the warnings, errors and fatal stop of a task's report as diagnostics
+    pub fn tag(&mut self, stage: &Stage, task: &str) {
+        for warning in &self.warnings {
+            self.diagnostics
+                .push(Diagnostic::new(Severity::Warning, warning, stage, task));
+        }
+        for error in &self.errors {
+            self.diagnostics.push(Diagnostic::new(Severity::Error, error, stage, task));
+        }
+        if let Some((true, error)) = &self.stop {
+            self.diagnostics.push(Diagnostic::new(Severity::Error, error, stage, task));
+        }
+    }
+
+    /// Prints the report and marks it as printed
     pub fn display(&mut self) {
+        self.print();
+        self.printed();
+    }
+
+    /// Prints the new warnings, the errors and the fatal stop if it was not printed before
+    pub fn print(&self) {
         for warning in &self.warnings {
             match warning {
                 HEMTTError::GENERIC(s, v) => {
@@ -51,13 +209,14 @@ impl Report {
                 HEMTTError::PATH(s) => {
                     warnmessage!(&s.source, format!("{:#?}", s.path));
                 }
+                HEMTTError::SCRIPT(s) => {
+                    warnmessage!(s.message(), s.info());
+                }
                 HEMTTError::TOML(s) => {
                     warn!(s);
                 }
             }
         }
-        self.old.append(&mut self.warnings);
-        self.warnings = Vec::new();
         for error in &self.errors {
             match error {
                 HEMTTError::GENERIC(s, v) => {
@@ -75,13 +234,15 @@ impl Report {
                 HEMTTError::PATH(s) => {
                     errormessage!(&s.source, format!("{:#?}", s.path));
                 }
+                HEMTTError::SCRIPT(s) => {
+                    errormessage!(s.message(), s.info());
+                }
                 HEMTTError::TOML(s) => {
                     error!(s);
                 }
             }
         }
         if !self.displayed_stop && self.stop.is_some() {
-            self.displayed_stop = true;
             let (fatal, error) = self.stop.as_ref().unwrap();
             if *fatal {
                 match error {
@@ -100,6 +261,9 @@ impl Report {
                     HEMTTError::PATH(s) => {
                         errormessage!(&s.source, format!("{:#?}", s.path));
                     }
+                    HEMTTError::SCRIPT(s) => {
+                        errormessage!(s.message(), s.info());
+                    }
                     HEMTTError::TOML(s) => {
                         error!(s);
                     }
@@ -108,6 +272,14 @@ impl Report {
         }
     }
 
+    /// Marks the warnings and the stop as printed so `print` does not repeat them
+    pub fn printed(&mut self) {
+        self.old.append(&mut self.warnings);
+        if self.stop.is_some() {
+            self.displayed_stop = true;
+        }
+    }
+
     /// Adds an error if it does not exist in the report
     pub fn unique_error(&mut self, error: HEMTTError) {
         match error {
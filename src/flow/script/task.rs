@@ -1,18 +1,27 @@
 use colored::*;
+use handlebars::to_json;
 use rayon::prelude::*;
 use regex::Regex;
 use subprocess::Exec;
 
-use crate::error::*;
-use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage, Task};
+use crate::flow::millis;
+use crate::{Addon, AddonList, HEMTTError, Project, Report, ScriptError, Stage, Task};
 
 #[derive(Clone)]
 pub struct Script {}
 impl Task for Script {
-    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, s: &Stage) -> AddonList {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn single(&self, addons: Vec<Result<(Report, Addon), HEMTTError>>, p: &Project, s: &Stage, dry_run: bool) -> AddonList {
         let steps = Script::get_scripts(s, p)?;
 
         for step in steps {
+            if dry_run {
+                dryrun!("Would run `{}` during {}", step, s);
+                continue;
+            }
             println!("{} `{}`", s.to_string().blue().bold(), step);
             Script::execute(&step, false, &addons, p, s)?;
         }
@@ -36,7 +45,7 @@ impl Script {
                 let args_re = Regex::new(r##"([^=\s"]*)=(?:"([^"\\]*(\\.[^"\\]*)*)"|'([^'\\]*(\\.[^'\\]*)*)'|([^"\s]+))|"([^"\\]*(\\.[^"\\]*)*)"|'([^'\\]*(\\.[^'\\]*)*)'|([^"\s]+)"##).unwrap();
                 let mut args: Vec<String> = vec!["hemtt".to_owned()];
                 for mat in args_re.find_iter(&cmd) {
-                    args.push(crate::render::run(mat.as_str(), Some(&s.to_string()), &p.get_variables())?);
+                    args.push(crate::render::run(mat.as_str(), Some(&s.to_string()), &p.get_variables()?)?);
                 }
                 crate::execute(&args, false)?;
             }
@@ -55,36 +64,43 @@ impl Script {
                     };
                     if script.foreach {
                         for step in steps {
-                            let exec = |data: &Result<(Report, Addon), HEMTTError>| {
-                                if let Ok((_, addon)) = data {
-                                    let step =
-                                        crate::render::run(step, Some(&format!("script:{}", &cmd)), &addon.get_variables(p))
-                                            .unwrap_or_print();
-                                    Script::execute(&step, script.show_output, addons, p, s).unwrap_or_print();
+                            let exec = |data: &Result<(Report, Addon), HEMTTError>| -> Result<(), HEMTTError> {
+                                if let Ok((report, addon)) = data {
+                                    let mut vars = addon.get_variables(p)?;
+                                    vars.insert("time", to_json(millis(report.time)));
+                                    for (name, stage) in &[
+                                        ("time_check", Stage::Check),
+                                        ("time_prebuild", Stage::PreBuild),
+                                        ("time_build", Stage::Build),
+                                    ] {
+                                        vars.insert(*name, to_json(millis(report.stage_time(stage))));
+                                    }
+                                    let step = crate::render::run(step, Some(&format!("script:{}", &cmd)), &vars)?;
+                                    Script::execute(&step, script.show_output, addons, p, s)?;
                                 }
+                                Ok(())
                             };
                             if script.parallel {
-                                addons.par_iter().for_each(exec);
+                                addons.par_iter().try_for_each(exec)?;
                             } else {
-                                addons.iter().for_each(exec);
+                                addons.iter().try_for_each(exec)?;
                             }
                         }
                     } else {
                         for step in steps {
-                            let step = crate::render::run(step, Some(&format!("script:{}", &cmd)), &p.get_variables())?;
+                            let step = crate::render::run(step, Some(&format!("script:{}", &cmd)), &p.get_variables()?)?;
                             Script::execute(&step, script.show_output, addons, p, s)?;
                         }
                     }
                 } else {
-                    error!("Script `{}` does not exist", &cmd);
-                    std::process::exit(3);
+                    return Err(HEMTTError::SCRIPT(ScriptError::Missing(cmd.to_string())));
                 }
             }
             _ => {
                 let cmd = command.to_string().replace("\\", "\\\\");
-                let shell = Exec::shell(crate::render::run(&command, Some(&s.to_string()), &p.get_variables())?)
+                let shell = Exec::shell(crate::render::run(&command, Some(&s.to_string()), &p.get_variables()?)?)
                     .capture()
-                    .unwrap_or_print();
+                    .map_err(|e| HEMTTError::generic("Failed to execute shell command", e.to_string()))?;
                 let out = &shell.stdout_str();
                 if output {
                     for line in out.lines() {
@@ -92,8 +108,7 @@ impl Script {
                     }
                 }
                 if !shell.success() {
-                    errormessage!("Failed to execute shell command", cmd);
-                    std::process::exit(2);
+                    return Err(HEMTTError::SCRIPT(ScriptError::Failed(cmd)));
                 }
             }
         }
@@ -107,9 +122,10 @@ impl Script {
             Stage::PostBuild => &p.postbuild,
             Stage::ReleaseBuild => &p.releasebuild,
             _ => {
-                // Invalid, we should never be here
-                println!("Scripts tried to run during an invalid stage, please report this");
-                unimplemented!()
+                return Err(HEMTTError::generic(
+                    format!("Scripts can not run during the `{}` stage", s),
+                    "Please report this",
+                ))
             }
         }
         .clone())
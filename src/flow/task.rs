@@ -1,19 +1,26 @@
-#[cfg(not(windows))]
-use indicatif::ProgressBar;
-#[cfg(windows)]
-use indicatif_windows::ProgressBar;
-
-use crate::{Addon, AddonList, HEMTTError, Project, Report, Stage};
+use crate::{Addon, AddonList, BuildEvents, HEMTTError, Project, Report, Stage};
 
 // A task is an independent item to be ran
 pub trait Task: objekt::Clone + std::marker::Send + std::marker::Sync {
+    /// Stable identifier used in reports
+    fn name(&self) -> &'static str;
     fn can_run(&self, _: &Addon, _: &Report, _: &Project, _: &Stage) -> Result<bool, HEMTTError> {
         Ok(false)
     }
-    fn parallel(&self, _: &Addon, _: &Report, _: &Project, _: &Stage, _: &ProgressBar) -> Result<Report, HEMTTError> {
+    /// Runs for each addon, `dry_run` tasks report what they would change without changing it
+    fn parallel(
+        &self,
+        _: &Addon,
+        _: &Report,
+        _: &Project,
+        _: &Stage,
+        _: &dyn BuildEvents,
+        _dry_run: bool,
+    ) -> Result<Report, HEMTTError> {
         unimplemented!()
     }
-    fn single(&self, _: Vec<Result<(Report, Addon), HEMTTError>>, _: &Project, _: &Stage) -> AddonList {
+    /// Runs once for every addon, `dry_run` as in `parallel`
+    fn single(&self, _: Vec<Result<(Report, Addon), HEMTTError>>, _: &Project, _: &Stage, _dry_run: bool) -> AddonList {
         unimplemented!()
     }
 }
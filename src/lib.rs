@@ -7,25 +7,32 @@ use clap::App;
 #[macro_use]
 pub mod macros;
 
+pub mod cache;
 pub mod commands;
 pub mod error;
 pub mod files;
 pub mod flow;
+pub mod output;
+pub mod pbo;
 pub mod project;
 pub mod render;
 mod startup;
+#[cfg(test)]
+mod test;
+pub mod trace;
 pub mod utilities;
 
 pub use build::addon::{Addon, AddonLocation};
 pub use commands::{build, Command};
-pub use error::{FileErrorLineNumber, HEMTTError, IOPathError};
+pub use error::{FileErrorLineNumber, HEMTTError, IOPathError, ScriptError};
 pub use files::{FileCache, RenderedFiles};
-pub use flow::{BuildScript, Flow, Report, Stage, Step, Task};
+pub use flow::{BuildEvents, BuildScript, Diagnostic, Flow, Report, Severity, Stage, Step, Task};
 pub use project::Project;
 
 pub type AddonList = Result<Vec<Result<(Report, Addon), HEMTTError>>, HEMTTError>;
 
 lazy_static::lazy_static! {
+    pub static ref BUILD_CACHE: Arc<Mutex<cache::BuildCache>> = Arc::new(Mutex::new(cache::BuildCache::read()));
     pub static ref CACHED: Arc<Mutex<FileCache>> = Arc::new(Mutex::new(FileCache::new()));
     pub static ref RENDERED: Arc<Mutex<RenderedFiles>> = Arc::new(Mutex::new(RenderedFiles::new()));
     pub static ref REPORTS: Arc<Mutex<HashMap<String, Report>>> = Arc::new(Mutex::new(HashMap::new()));
@@ -107,9 +114,16 @@ pub fn execute(input: &[String], root: bool) -> Result<(), HEMTTError> {
         .arg(
             clap::Arg::with_name("time")
                 .global(true)
-                .help("Time the execution")
+                .help("Time the execution and list the slowest addons")
                 .long("time"),
         )
+        .arg(
+            clap::Arg::with_name("trace")
+                .global(true)
+                .help("Write a Chrome trace of the execution to the file")
+                .takes_value(true)
+                .long("trace"),
+        )
         .arg(
             clap::Arg::with_name("ci") // This is not actually checked by clap, see lib.rs
                 .global(true)
@@ -134,6 +148,14 @@ pub fn execute(input: &[String], root: bool) -> Result<(), HEMTTError> {
     commands.push(Box::new(commands::Clean {}));
     commands.push(Box::new(commands::Status {}));
     commands.push(Box::new(commands::Update {}));
+    commands.push(Box::new(commands::Watch {}));
+    commands.push(Box::new(commands::Pbo {}));
+    commands.push(Box::new(commands::Diff {}));
+    commands.push(Box::new(commands::Verify {}));
+    commands.push(Box::new(commands::Keys {}));
+    commands.push(Box::new(commands::Sign {}));
+    commands.push(Box::new(commands::Changelog {}));
+    commands.push(Box::new(commands::Version {}));
 
     // Add utilities here
     commands.push(Box::new(utilities::Translation {}));
@@ -151,7 +173,16 @@ pub fn execute(input: &[String], root: bool) -> Result<(), HEMTTError> {
         app = app.subcommand(sub);
     }
 
-    let matches = app.get_matches_from(input);
+    let matches = match app.get_matches_from_safe(input) {
+        Ok(matches) => matches,
+        Err(e) => match e.kind {
+            clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed => {
+                println!("{}", e.message);
+                return Ok(());
+            }
+            _ => return Err(HEMTTError::simple(e.message)),
+        },
+    };
 
     let start = if matches.is_present("time") {
         Some(Instant::now())
@@ -167,36 +198,55 @@ pub fn execute(input: &[String], root: bool) -> Result<(), HEMTTError> {
                 num_cpus::get()
             })
             .build_global()
-            .unwrap();
+            .map_err(|e| HEMTTError::generic("Unable to create the thread pool", e.to_string()))?;
     }
 
-    match matches.subcommand_name() {
+    let trace = if root { matches.value_of("trace") } else { None };
+    if trace.is_some() {
+        trace::enable();
+    }
+
+    let result = match matches.subcommand_name() {
         Some(v) => match hash_commands.get(v) {
             Some(c) => {
+                let _span = trace::span(v, "command");
                 let sub_matches = matches.subcommand_matches(v).unwrap();
                 if c.require_project() {
-                    let project = Project::read()?;
-                    if root {
-                        println!("HEMTT {}", *crate::VERSION);
-                        println!("Environment: {}", project::environment());
-                        println!();
-                        startup::startup();
-                    }
-                    c.run(sub_matches, project)?;
+                    Project::read().and_then(|project| {
+                        if root {
+                            println!("HEMTT {}", *crate::VERSION);
+                            println!("Environment: {}", project::environment());
+                            println!();
+                            startup::startup()?;
+                        }
+                        c.run(sub_matches, project)
+                    })
                 } else {
-                    c.run_no_project(sub_matches)?;
+                    c.run_no_project(sub_matches)
                 }
             }
-            None => println!("No command"),
+            None => {
+                println!("No command");
+                Ok(())
+            }
         },
-        None => println!("No command"),
-    }
+        None => {
+            println!("No command");
+            Ok(())
+        }
+    };
 
     crate::RENDERED.lock().unwrap().clean();
 
+    // Written even when the command fails, a failed build is often the one worth looking at
+    if let Some(path) = trace {
+        trace::write(path)?;
+    }
+    result?;
+
     if matches.is_present("time") {
         let elapsed = start.unwrap().elapsed();
-        println!("Execution Took {}.{} Seconds", elapsed.as_secs(), elapsed.as_millis());
+        println!("Execution Took {}.{:03} Seconds", elapsed.as_secs(), elapsed.subsec_millis());
     }
 
     Ok(())
@@ -53,6 +53,32 @@ macro_rules! debug {
     }};
 }
 
+/// Locks a global mutex, or any mutex with a label for the trace, tracing the time spent waiting for it
+#[macro_export]
+macro_rules! lock {
+    ($m:ident) => {{
+        let _span = crate::trace::span(concat!("Waiting for ", stringify!($m)), "lock");
+        crate::$m.lock().unwrap()
+    }};
+    ($m:expr, $label:literal) => {{
+        let _span = crate::trace::span(concat!("Waiting for ", $label), "lock");
+        $m.lock().unwrap()
+    }};
+}
+
+#[macro_export]
+macro_rules! dryrun {
+    ($s:expr) => {{
+        use colored::*;
+        let style = "dry-run".cyan().bold();
+        let status = $s;
+        crate::iprintln!("{style}: {status}", style, status);
+    }};
+    ($s:expr, $($p:expr),*) => {{
+        dryrun!(format!($s, $($p,)*));
+    }};
+}
+
 #[macro_export]
 macro_rules! warn {
     ($s:expr) => {{
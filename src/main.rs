@@ -6,8 +6,6 @@ pub mod macros;
 
 use hemtt::*;
 
-use crate::error::PrintableError;
-
 fn main() {
     if cfg!(windows) {
         ansi_support();
@@ -15,7 +13,19 @@ fn main() {
 
     let args: Vec<_> = std::env::args().collect();
 
-    crate::execute(&args, true).unwrap_or_print();
+    if let Err(error) = crate::execute(&args, true) {
+        error!(format!("{}", error));
+        std::process::exit(exit_code(&error));
+    }
+}
+
+/// Exit codes HEMTT used before errors were returned to `main`
+fn exit_code(error: &HEMTTError) -> i32 {
+    match error {
+        HEMTTError::SCRIPT(ScriptError::Failed(_)) => 2,
+        HEMTTError::SCRIPT(ScriptError::Missing(_)) => 3,
+        _ => 1,
+    }
 }
 
 #[cfg(windows)]
@@ -0,0 +1,84 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::flow::millis;
+use crate::{Addon, Diagnostic, HEMTTError, Project, Report, Severity, Stage};
+
+#[derive(Serialize)]
+struct BuildReport<'a> {
+    hemtt: &'a str,
+    project: &'a str,
+    version: Option<String>,
+    addons: Vec<AddonReport<'a>>,
+    /// Errors that could not be tied to an addon
+    errors: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+struct AddonReport<'a> {
+    name: &'a str,
+    location: String,
+    folder: String,
+    target: String,
+    /// `success`, `skipped` or `failed`
+    status: &'static str,
+    reason: Option<String>,
+    /// Milliseconds spent running tasks
+    time: u64,
+    tasks: Vec<TaskReport>,
+    diagnostics: &'a [Diagnostic],
+}
+
+#[derive(Serialize)]
+struct TaskReport {
+    stage: String,
+    task: &'static str,
+    /// Milliseconds spent running the task
+    time: u64,
+}
+
+pub fn write<W: Write>(out: &mut W, addons: &[Result<(Report, Addon), HEMTTError>], p: &Project) -> Result<(), HEMTTError> {
+    let mut report = BuildReport {
+        hemtt: *crate::VERSION,
+        project: &p.name,
+        version: p.version().ok(),
+        addons: Vec::new(),
+        errors: Vec::new(),
+    };
+    for data in addons {
+        match data {
+            Ok((r, addon)) => {
+                let (status, reason) = match &r.stop {
+                    None => ("success", r.rebuild.clone()),
+                    Some((fatal, error)) => (
+                        if *fatal { "failed" } else { "skipped" },
+                        Some(Diagnostic::new(Severity::Error, error, &Stage::None, "").message),
+                    ),
+                };
+                report.addons.push(AddonReport {
+                    name: &addon.name,
+                    location: addon.location.to_string(),
+                    folder: addon.folder().display().to_string(),
+                    target: addon.target(p).display().to_string(),
+                    status,
+                    reason,
+                    time: millis(r.time),
+                    tasks: r
+                        .timings
+                        .iter()
+                        .map(|timing| TaskReport {
+                            stage: timing.stage.to_string(),
+                            task: timing.task,
+                            time: millis(timing.time),
+                        })
+                        .collect(),
+                    diagnostics: &r.diagnostics,
+                });
+            }
+            Err(e) => report.errors.push(Diagnostic::new(Severity::Error, e, &Stage::None, "")),
+        }
+    }
+    serde_json::to_writer_pretty(out, &report)?;
+    Ok(())
+}
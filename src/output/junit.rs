@@ -0,0 +1,108 @@
+use std::io::Write;
+
+use crate::{Addon, Diagnostic, HEMTTError, Project, Report, Severity, Stage};
+
+pub fn write<W: Write>(out: &mut W, addons: &[Result<(Report, Addon), HEMTTError>], p: &Project) -> Result<(), HEMTTError> {
+    let mut cases = Vec::new();
+    let (mut failures, mut skipped) = (0, 0);
+    let mut total_time = 0.0;
+
+    for data in addons {
+        match data {
+            Ok((report, addon)) => {
+                let time = report.time.as_secs() as f64 + f64::from(report.time.subsec_millis()) / 1000.0;
+                total_time += time;
+                let mut case = format!(
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+                    escape(&addon.location.to_string()),
+                    escape(&addon.name),
+                    time
+                );
+                let errors: Vec<&Diagnostic> = report.diagnostics.iter().filter(|d| d.severity == Severity::Error).collect();
+                let warnings: Vec<&Diagnostic> = report
+                    .diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Severity::Warning)
+                    .collect();
+                if !errors.is_empty() {
+                    failures += 1;
+                    case.push_str(&format!(
+                        "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+                        escape(&errors[0].message),
+                        super::rule(&errors[0].task).1,
+                        escape(&errors.iter().map(|d| describe(d)).collect::<Vec<_>>().join("\n"))
+                    ));
+                } else if let Some((_, error)) = &report.stop {
+                    skipped += 1;
+                    case.push_str(&format!(
+                        "      <skipped message=\"{}\"/>\n",
+                        escape(&Diagnostic::new(Severity::Warning, error, &Stage::None, "").message)
+                    ));
+                }
+                if !warnings.is_empty() {
+                    case.push_str(&format!(
+                        "      <system-out>{}</system-out>\n",
+                        escape(&warnings.iter().map(|d| describe(d)).collect::<Vec<_>>().join("\n"))
+                    ));
+                }
+                case.push_str("    </testcase>\n");
+                cases.push(case);
+            }
+            Err(e) => {
+                failures += 1;
+                let diagnostic = Diagnostic::new(Severity::Error, e, &Stage::None, "");
+                cases.push(format!(
+                    "    <testcase classname=\"hemtt\" name=\"error\">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    escape(&diagnostic.message),
+                    escape(&describe(&diagnostic))
+                ));
+            }
+        }
+    }
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(out, "<testsuites>")?;
+    writeln!(
+        out,
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" errors=\"0\" time=\"{:.3}\">",
+        escape(&p.name),
+        cases.len(),
+        failures,
+        skipped,
+        total_time
+    )?;
+    for case in cases {
+        write!(out, "{}", case)?;
+    }
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")?;
+    Ok(())
+}
+
+/// `file:line:column: message` in the style of compiler output
+fn describe(diagnostic: &Diagnostic) -> String {
+    let mut text = String::new();
+    if let Some(file) = &diagnostic.file {
+        text.push_str(file);
+        if let Some(line) = diagnostic.line {
+            text.push_str(&format!(":{}", line));
+            if let Some(column) = diagnostic.column {
+                text.push_str(&format!(":{}", column));
+            }
+        }
+        text.push_str(": ");
+    }
+    text.push_str(&diagnostic.message);
+    if let Some(info) = &diagnostic.info {
+        text.push_str(&format!(" ({})", info));
+    }
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,227 @@
+use std::path::PathBuf;
+
+use crate::{Addon, HEMTTError, Project, Report};
+
+mod json;
+mod junit;
+mod sarif;
+
+/// Formats a report can be written in
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    JUnit,
+    Sarif,
+}
+
+/// Stable rule ids for every task, as (task, id, description)
+///
+/// New tasks are added at the end so existing ids never change
+pub static RULES: &[(&str, &str, &str)] = &[
+    ("", "HEMTT0000", "Unexpected error"),
+    ("not_empty", "HEMTT1001", "Addon folder is empty"),
+    ("valid_name", "HEMTT1002", "Addon name does not follow standards"),
+    ("render", "HEMTT1003", "File could not be rendered"),
+    ("preprocess", "HEMTT1004", "Config could not be preprocessed"),
+    ("build", "HEMTT1005", "PBO could not be packed"),
+    ("cache", "HEMTT1006", "Build cache could not be checked"),
+    ("save_cache", "HEMTT1007", "Build cache could not be saved"),
+    ("clear", "HEMTT1008", "Built PBO could not be removed"),
+    ("clean", "HEMTT1009", "Leftover PBO could not be removed"),
+    ("script", "HEMTT1010", "Build script failed"),
+    ("release", "HEMTT1011", "Release could not be created"),
+    ("changelog", "HEMTT1012", "Changelog could not be generated"),
+    ("sign", "HEMTT1013", "PBO could not be signed"),
+    ("verify", "HEMTT1014", "Signature could not be verified"),
+    ("manifest", "HEMTT1015", "Manifest could not be written"),
+];
+
+/// Index and id of the rule for a task
+pub fn rule(task: &str) -> (usize, &'static str) {
+    let index = RULES.iter().position(|(name, _, _)| *name == task).unwrap_or(0);
+    (index, RULES[index].1)
+}
+
+/// A report to write once the flow has finished
+#[derive(Clone, Debug)]
+pub struct Target {
+    pub format: Format,
+    pub path: PathBuf,
+}
+
+/// Argument used to request reports from a command
+pub fn report_arg<'a, 'b>() -> clap::Arg<'a, 'b> {
+    clap::Arg::with_name("report")
+        .help("Write a report as `<format>=<path>`, supported formats: json, sarif, junit")
+        .long("report")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+}
+
+/// Reports requested with `report_arg`, parsed before the flow runs so mistakes are caught early
+pub fn targets(args: &clap::ArgMatches) -> Result<Vec<Target>, HEMTTError> {
+    let mut targets = Vec::new();
+    if let Some(values) = args.values_of("report") {
+        for value in values {
+            let mut split = value.splitn(2, '=');
+            let format = split.next().unwrap();
+            let path = split.next().ok_or_else(|| {
+                HEMTTError::generic(
+                    format!("Invalid report `{}`", value),
+                    "Use `<format>=<path>`, for example `json=report.json`",
+                )
+            })?;
+            let format = match format {
+                "json" => Format::Json,
+                "junit" => Format::JUnit,
+                "sarif" => Format::Sarif,
+                _ => {
+                    return Err(HEMTTError::generic(
+                        format!("Unknown report format `{}`", format),
+                        "Supported formats: json, sarif, junit",
+                    ))
+                }
+            };
+            targets.push(Target {
+                format,
+                path: PathBuf::from(path),
+            });
+        }
+    }
+    Ok(targets)
+}
+
+/// Writes the reports of the addons processed by a flow
+pub fn write(targets: &[Target], addons: &[Result<(Report, Addon), HEMTTError>], p: &Project) -> Result<(), HEMTTError> {
+    for target in targets {
+        let mut out = create_file!(target.path)?;
+        match target.format {
+            Format::Json => json::write(&mut out, addons, p)?,
+            Format::JUnit => junit::write(&mut out, addons, p)?,
+            Format::Sarif => sarif::write(&mut out, addons, p)?,
+        }
+        debug!("Wrote report `{}`", target.path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::value::Value as Json;
+
+    use super::*;
+    use crate::{AddonLocation, FileErrorLineNumber, Stage};
+
+    fn project() -> Project {
+        Project::new("Test & Co".to_owned(), "test".to_owned(), "Tester".to_owned(), String::new())
+    }
+
+    fn addon(name: &str) -> Addon {
+        Addon {
+            name: name.to_owned(),
+            location: AddonLocation::Addons,
+        }
+    }
+
+    /// A built addon, an addon that failed to preprocess and an error from outside the addons
+    fn addons() -> Vec<Result<(Report, Addon), HEMTTError>> {
+        let mut built = Report::new();
+        built.rebuild = Some("Not built before".to_owned());
+        built.timed(&Stage::Build, "build", Duration::from_millis(1500));
+
+        let mut failed = Report::new();
+        failed.warnings.push(HEMTTError::LINENO(FileErrorLineNumber {
+            file: "addons\\broken\\config.cpp".to_owned(),
+            content: String::new(),
+            error: "Unknown <macro>".to_owned(),
+            line: Some(0),
+            col: Some(0),
+            note: None,
+        }));
+        failed.stop = Some((
+            true,
+            HEMTTError::generic("Unable to preprocess `config.cpp`", "Missing include"),
+        ));
+        failed.tag(&Stage::PreBuild, "preprocess");
+
+        vec![
+            Ok((built, addon("main"))),
+            Ok((failed, addon("broken"))),
+            Err(HEMTTError::simple("Unable to run the release script")),
+        ]
+    }
+
+    #[test]
+    fn rules() {
+        for (i, (task, id, _)) in RULES.iter().enumerate() {
+            assert_eq!((i, *id), rule(task));
+            assert_eq!(1, RULES.iter().filter(|(_, other, _)| other == id).count());
+        }
+        assert_eq!((0, "HEMTT0000"), rule("unknown"));
+    }
+
+    #[test]
+    fn json() {
+        let mut out = Vec::new();
+        json::write(&mut out, &addons(), &project()).unwrap();
+        let report: Json = serde_json::from_slice(&out).unwrap();
+        assert_eq!("Test & Co", report["project"]);
+        assert_eq!("success", report["addons"][0]["status"]);
+        assert_eq!("Not built before", report["addons"][0]["reason"]);
+        assert_eq!(1500, report["addons"][0]["time"]);
+        assert_eq!("build", report["addons"][0]["tasks"][0]["task"]);
+        assert_eq!("Build", report["addons"][0]["tasks"][0]["stage"]);
+        assert_eq!("failed", report["addons"][1]["status"]);
+        assert_eq!("Unable to preprocess `config.cpp`", report["addons"][1]["reason"]);
+        assert_eq!("warning", report["addons"][1]["diagnostics"][0]["severity"]);
+        assert_eq!("error", report["addons"][1]["diagnostics"][1]["severity"]);
+        assert_eq!("preprocess", report["addons"][1]["diagnostics"][1]["task"]);
+        assert_eq!("Unable to run the release script", report["errors"][0]["message"]);
+    }
+
+    #[test]
+    fn sarif() {
+        let mut out = Vec::new();
+        sarif::write(&mut out, &addons(), &project()).unwrap();
+        let report: Json = serde_json::from_slice(&out).unwrap();
+        let run = &report["runs"][0];
+        assert_eq!(RULES.len(), run["tool"]["driver"]["rules"].as_array().unwrap().len());
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(3, results.len());
+
+        assert_eq!("warning", results[0]["level"]);
+        assert_eq!("HEMTT1004", results[0]["ruleId"]);
+        assert_eq!(4, results[0]["ruleIndex"]);
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!("addons/broken/config.cpp", location["artifactLocation"]["uri"]);
+        assert_eq!(1, location["region"]["startLine"]);
+        assert_eq!(1, location["region"]["startColumn"]);
+
+        assert_eq!("error", results[1]["level"]);
+        assert_eq!(
+            "Unable to preprocess `config.cpp`\nMissing include",
+            results[1]["message"]["text"]
+        );
+        assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+
+        assert_eq!("HEMTT0000", results[2]["ruleId"]);
+        assert_eq!(".", results[2]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]);
+    }
+
+    #[test]
+    fn junit() {
+        let mut out = Vec::new();
+        junit::write(&mut out, &addons(), &project()).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.contains(
+            "<testsuite name=\"Test &amp; Co\" tests=\"3\" failures=\"2\" skipped=\"0\" errors=\"0\" time=\"1.500\">"
+        ));
+        assert!(report.contains("<testcase classname=\"addons\" name=\"main\" time=\"1.500\">\n    </testcase>"));
+        assert!(report.contains("<failure message=\"Unable to preprocess `config.cpp`\" type=\"HEMTT1004\">"));
+        assert!(report.contains("<system-out>addons\\broken\\config.cpp:0:0: Unknown &lt;macro&gt;</system-out>"));
+        assert!(report.contains("<testcase classname=\"hemtt\" name=\"error\">"));
+    }
+}
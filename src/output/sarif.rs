@@ -0,0 +1,87 @@
+use std::io::Write;
+
+use serde_json::json;
+use serde_json::value::Value as Json;
+
+use crate::{Addon, Diagnostic, HEMTTError, Project, Report, Severity, Stage};
+
+static SCHEMA: &str = "https://schemastore.azurewebsites.net/schemas/json/sarif-2.1.0-rtm.5.json";
+
+pub fn write<W: Write>(out: &mut W, addons: &[Result<(Report, Addon), HEMTTError>], _: &Project) -> Result<(), HEMTTError> {
+    let rules: Vec<Json> = super::RULES
+        .iter()
+        .map(|(_, id, description)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for data in addons {
+        match data {
+            Ok((report, addon)) => {
+                for diagnostic in &report.diagnostics {
+                    results.push(result(diagnostic, &addon.folder().display().to_string()));
+                }
+            }
+            Err(e) => {
+                results.push(result(&Diagnostic::new(Severity::Error, e, &Stage::None, ""), "."));
+            }
+        }
+    }
+
+    serde_json::to_writer_pretty(
+        out,
+        &json!({
+            "$schema": SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "HEMTT",
+                        "version": *crate::VERSION,
+                        "informationUri": "https://github.com/synixebrett/HEMTT",
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        }),
+    )?;
+    Ok(())
+}
+
+fn result(diagnostic: &Diagnostic, folder: &str) -> Json {
+    let (index, id) = super::rule(&diagnostic.task);
+    let message = match &diagnostic.info {
+        Some(info) => format!("{}\n{}", diagnostic.message, info),
+        None => diagnostic.message.clone(),
+    };
+    let mut location = json!({
+        "physicalLocation": {
+            "artifactLocation": {
+                "uri": diagnostic.file.as_ref().map(String::as_str).unwrap_or(folder).replace("\\", "/"),
+            },
+        },
+    });
+    if let Some(line) = diagnostic.line {
+        // SARIF lines and columns start at 1, some parsers report errors on line 0
+        let mut region = json!({ "startLine": line.max(1) });
+        if let Some(column) = diagnostic.column {
+            region["startColumn"] = json!(column.max(1));
+        }
+        location["physicalLocation"]["region"] = region;
+    }
+    json!({
+        "ruleId": id,
+        "ruleIndex": index,
+        "level": match diagnostic.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        },
+        "message": { "text": message },
+        "locations": [location],
+    })
+}
@@ -0,0 +1,97 @@
+use crate::HEMTTError;
+
+/// Decompresses the LZSS data of a `Cprs` entry into `size` bytes
+///
+/// Every flag byte is followed by 8 items, a set bit is a literal byte and a clear bit is a 2 byte reference to the
+/// output so far. The decompressed data is followed by the sum of its bytes.
+pub fn decompress(data: &[u8], size: usize) -> Result<Vec<u8>, HEMTTError> {
+    let mut output: Vec<u8> = Vec::with_capacity(size);
+    let mut cursor = 0;
+    'outer: while output.len() < size {
+        let flags = next(data, &mut cursor)?;
+        for bit in 0..8 {
+            if output.len() >= size {
+                break 'outer;
+            }
+            if flags & (1 << bit) != 0 {
+                output.push(next(data, &mut cursor)?);
+                continue;
+            }
+            let low = next(data, &mut cursor)? as usize;
+            let high = next(data, &mut cursor)? as usize;
+            let distance = low | ((high & 0xF0) << 4);
+            let length = (high & 0x0F) + 3;
+            if distance == 0 {
+                return Err(HEMTTError::simple("The compressed data references itself"));
+            }
+            // References before the start of the data are filled with spaces
+            let start = output.len() as isize - distance as isize;
+            for i in 0..length {
+                if output.len() >= size {
+                    break;
+                }
+                let position = start + i as isize;
+                let byte = if position < 0 { b' ' } else { output[position as usize] };
+                output.push(byte);
+            }
+        }
+    }
+
+    if cursor + 4 > data.len() {
+        return Err(HEMTTError::simple(
+            "The compressed data is truncated, the checksum is missing",
+        ));
+    }
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[cursor..cursor + 4]);
+    let checksum = output.iter().fold(0u32, |sum, byte| sum.wrapping_add(u32::from(*byte)));
+    if u32::from_le_bytes(bytes) != checksum {
+        return Err(HEMTTError::simple("The checksum of the compressed data does not match"));
+    }
+    Ok(output)
+}
+
+fn next(data: &[u8], cursor: &mut usize) -> Result<u8, HEMTTError> {
+    let byte = *data
+        .get(*cursor)
+        .ok_or_else(|| HEMTTError::simple("The compressed data is truncated"))?;
+    *cursor += 1;
+    Ok(byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_checksum(mut data: Vec<u8>, output: &[u8]) -> Vec<u8> {
+        let checksum = output.iter().fold(0u32, |sum, byte| sum.wrapping_add(u32::from(*byte)));
+        data.extend_from_slice(&checksum.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn literals() {
+        let data = with_checksum(vec![0b0000_0111, b'a', b'b', b'c'], b"abc");
+        assert_eq!(b"abc".to_vec(), decompress(&data, 3).unwrap());
+    }
+
+    #[test]
+    fn references() {
+        // Three literals, then 6 bytes from 3 bytes back, overlapping what is being written
+        let data = with_checksum(vec![0b0000_0111, b'a', b'b', b'c', 0x03, 0x03], b"abcabcabc");
+        assert_eq!(b"abcabcabc".to_vec(), decompress(&data, 9).unwrap());
+    }
+
+    #[test]
+    fn spaces() {
+        let data = with_checksum(vec![0b0000_0010, 0x02, 0x00, b'a'], b"   a");
+        assert_eq!(b"   a".to_vec(), decompress(&data, 4).unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(decompress(&[0b0000_0111, b'a'], 3).is_err());
+        assert!(decompress(&[0b0000_0111, b'a', b'b', b'c'], 3).is_err());
+        assert!(decompress(&[0b0000_0111, b'a', b'b', b'c', 0, 0, 0, 0], 3).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use sha1::{Digest, Sha1};
+
+use crate::HEMTTError;
+
+mod lzss;
+mod read;
+pub use lzss::decompress;
+pub use read::{PBOEntry, ReadablePBO};
+
+/// `Vers`, marks the header that holds the extensions
+static VERSION_METHOD: u32 = 0x5665_7273;
+/// `Cprs`, the entry is compressed
+pub static COMPRESSED: u32 = 0x4370_7273;
+
+/// A PBO that is written the same way for the same files, regardless of the order they were added
+#[derive(Debug, Default)]
+pub struct WritablePBO {
+    files: BTreeMap<String, Vec<u8>>,
+    extensions: BTreeMap<String, String>,
+}
+
+impl WritablePBO {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, `/` in the name is replaced with `\`
+    pub fn add_file<S: AsRef<str>>(&mut self, name: S, data: Vec<u8>) {
+        self.files.insert(normalize(name.as_ref()), data);
+    }
+
+    pub fn add_extension<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.extensions.insert(key.into(), value.into());
+    }
+
+    /// Writes the PBO, files are sorted by name and every timestamp is 0
+    pub fn write<W: Write>(&self, output: &mut W) -> Result<(), HEMTTError> {
+        let mut buf = Vec::new();
+
+        write_header(&mut buf, "", VERSION_METHOD, 0)?;
+        // The prefix is always first, the rest are sorted by the map
+        if let Some(prefix) = self.extensions.get("prefix") {
+            write_cstring(&mut buf, "prefix")?;
+            write_cstring(&mut buf, prefix)?;
+        }
+        for (key, value) in &self.extensions {
+            if key == "prefix" {
+                continue;
+            }
+            write_cstring(&mut buf, key)?;
+            write_cstring(&mut buf, value)?;
+        }
+        write_cstring(&mut buf, "")?;
+
+        let mut files: Vec<(&String, &Vec<u8>)> = self.files.iter().collect();
+        files.sort_by(|(a, _), (b, _)| a.to_lowercase().cmp(&b.to_lowercase()).then(a.cmp(b)));
+        for (name, data) in &files {
+            write_header(&mut buf, name, 0, data.len() as u32)?;
+        }
+        write_header(&mut buf, "", 0, 0)?;
+        for (_, data) in &files {
+            buf.write_all(data)?;
+        }
+
+        let checksum = Sha1::digest(&buf);
+        buf.write_all(&[0])?;
+        buf.write_all(&checksum)?;
+        output.write_all(&buf)?;
+        Ok(())
+    }
+}
+
+/// PBOs always use `\` as the separator
+pub fn normalize(name: &str) -> String {
+    name.replace('/', "\\")
+}
+
+fn write_cstring<W: Write>(output: &mut W, s: &str) -> Result<(), HEMTTError> {
+    output.write_all(s.as_bytes())?;
+    output.write_all(&[0])?;
+    Ok(())
+}
+
+fn write_header<W: Write>(output: &mut W, name: &str, method: u32, size: u32) -> Result<(), HEMTTError> {
+    write_cstring(output, name)?;
+    // Packing method, original size, reserved, timestamp and data size
+    for value in &[method, size, 0, 0, size] {
+        output.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(pbo: &WritablePBO) -> Vec<u8> {
+        let mut data = Vec::new();
+        pbo.write(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn reproducible() {
+        let mut first = WritablePBO::new();
+        first.add_extension("prefix", "z\\addons\\main");
+        first.add_extension("version", "1.0.0");
+        first.add_file("config.bin", b"config".to_vec());
+        first.add_file("functions/fnc_b.sqf", b"b".to_vec());
+        first.add_file("functions/fnc_a.sqf", b"a".to_vec());
+
+        let mut second = WritablePBO::new();
+        second.add_file("functions\\fnc_a.sqf", b"a".to_vec());
+        second.add_file("functions\\fnc_b.sqf", b"b".to_vec());
+        second.add_file("config.bin", b"config".to_vec());
+        second.add_extension("version", "1.0.0");
+        second.add_extension("prefix", "z\\addons\\main");
+
+        assert_eq!(written(&first), written(&second));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut pbo = WritablePBO::new();
+        pbo.add_extension("version", "1.0.0");
+        pbo.add_extension("prefix", "z\\addons\\main");
+        pbo.add_file("functions/fnc_a.sqf", b"a".to_vec());
+        pbo.add_file("Config.bin", b"config".to_vec());
+
+        let read = ReadablePBO::read(&mut written(&pbo).as_slice()).unwrap();
+        assert_eq!(
+            vec![
+                ("prefix".to_owned(), "z\\addons\\main".to_owned()),
+                ("version".to_owned(), "1.0.0".to_owned()),
+            ],
+            read.extensions
+        );
+        let names: Vec<&str> = read.entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(vec!["Config.bin", "functions\\fnc_a.sqf"], names);
+        assert!(read.entries.iter().all(|entry| entry.timestamp == 0));
+        assert_eq!(b"config", read.data(&read.entries[0]));
+        assert_eq!(b"a", read.data(&read.entries[1]));
+        assert_eq!(Some(true), read.checksum_valid());
+    }
+}
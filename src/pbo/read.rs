@@ -0,0 +1,128 @@
+use std::io::Read;
+
+use sha1::{Digest, Sha1};
+
+use super::VERSION_METHOD;
+use crate::HEMTTError;
+
+/// A file header from a PBO
+#[derive(Clone, Debug)]
+pub struct PBOEntry {
+    pub name: String,
+    pub packing_method: u32,
+    pub original_size: u32,
+    pub timestamp: u32,
+    pub data_size: u32,
+    /// Position of the data from the start of the PBO
+    pub offset: usize,
+}
+
+/// A PBO read into memory along with the layout of its headers
+#[derive(Debug)]
+pub struct ReadablePBO {
+    /// Header extensions in the order they are stored
+    pub extensions: Vec<(String, String)>,
+    pub entries: Vec<PBOEntry>,
+    /// SHA-1 stored at the end of the PBO
+    pub checksum: Option<Vec<u8>>,
+    /// SHA-1 of everything before the stored checksum
+    pub computed: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl ReadablePBO {
+    pub fn read<R: Read>(input: &mut R) -> Result<Self, HEMTTError> {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let mut cursor = 0;
+        let mut extensions = Vec::new();
+        let mut headers = Vec::new();
+        loop {
+            let name = read_cstring(&data, &mut cursor)?;
+            let mut values = [0u32; 5];
+            for value in values.iter_mut() {
+                *value = read_u32(&data, &mut cursor)?;
+            }
+            let [packing_method, original_size, _, timestamp, data_size] = values;
+            if name.is_empty() && packing_method == VERSION_METHOD {
+                loop {
+                    let key = read_cstring(&data, &mut cursor)?;
+                    if key.is_empty() {
+                        break;
+                    }
+                    let value = read_cstring(&data, &mut cursor)?;
+                    extensions.push((key, value));
+                }
+                continue;
+            }
+            if name.is_empty() {
+                break;
+            }
+            headers.push((name, packing_method, original_size, timestamp, data_size));
+        }
+
+        let mut entries = Vec::new();
+        for (name, packing_method, original_size, timestamp, data_size) in headers {
+            entries.push(PBOEntry {
+                name,
+                packing_method,
+                original_size,
+                timestamp,
+                data_size,
+                offset: cursor,
+            });
+            cursor += data_size as usize;
+        }
+        if cursor > data.len() {
+            return Err(HEMTTError::simple("The PBO is truncated, the files are larger than the PBO"));
+        }
+
+        let (checksum, computed) = if data.len() == cursor + 21 && data[cursor] == 0 {
+            (Some(data[cursor + 1..].to_vec()), Sha1::digest(&data[..cursor]).to_vec())
+        } else {
+            (None, Sha1::digest(&data).to_vec())
+        };
+
+        Ok(Self {
+            extensions,
+            entries,
+            checksum,
+            computed,
+            data,
+        })
+    }
+
+    pub fn extension(&self, key: &str) -> Option<&str> {
+        self.extensions.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the stored checksum matches the contents, `None` if there is no checksum
+    pub fn checksum_valid(&self) -> Option<bool> {
+        self.checksum.as_ref().map(|checksum| checksum == &self.computed)
+    }
+
+    /// Contents of an entry as stored in the PBO
+    pub fn data(&self, entry: &PBOEntry) -> &[u8] {
+        &self.data[entry.offset..entry.offset + entry.data_size as usize]
+    }
+}
+
+fn read_cstring(data: &[u8], cursor: &mut usize) -> Result<String, HEMTTError> {
+    let end = data[*cursor..]
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| HEMTTError::simple("The PBO is truncated, a header name is not terminated"))?;
+    let s = String::from_utf8_lossy(&data[*cursor..*cursor + end]).to_string();
+    *cursor += end + 1;
+    Ok(s)
+}
+
+fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, HEMTTError> {
+    if *cursor + 4 > data.len() {
+        return Err(HEMTTError::simple("The PBO is truncated, a header is incomplete"));
+    }
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[*cursor..*cursor + 4]);
+    *cursor += 4;
+    Ok(u32::from_le_bytes(bytes))
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use git2::{Oid, Repository, Sort, StatusOptions};
+use regex::Regex;
+
+use crate::HEMTTError;
+
+/// The version is needed for every addon, the history is only walked once for each project
+#[derive(Clone, Default)]
+pub struct GitVersion(Arc<Mutex<Option<String>>>);
+
+impl GitVersion {
+    pub fn get(&self) -> Result<String, HEMTTError> {
+        let mut cached = self.0.lock().map_err(|_| {
+            HEMTTError::simple("Unable to read the version from git, another thread failed while reading it")
+        })?;
+        if let Some(version) = &*cached {
+            return Ok(version.clone());
+        }
+        let version = version(Path::new("."))?;
+        *cached = Some(version.clone());
+        Ok(version)
+    }
+
+    /// Forgets the version, the next `get` reads the repository again
+    pub fn clear(&self) {
+        if let Ok(mut cached) = self.0.lock() {
+            *cached = None;
+        }
+    }
+}
+
+/// `major.minor.patch.build` from the closest semver tag, with the commits since the tag as build
+/// and `-dirty` appended when tracked files have uncommitted changes
+pub fn version(root: &Path) -> Result<String, HEMTTError> {
+    let repo = Repository::open(root)?;
+    let semver = Regex::new(r"^v?(\d+)\.(\d+)\.(\d+)$").unwrap();
+    let mut tags: HashMap<Oid, (u32, u32, u32)> = HashMap::new();
+    for name in repo.tag_names(None)?.iter().filter_map(|n| n) {
+        if let Some(c) = semver.captures(name) {
+            let commit = repo.revparse_single(&format!("refs/tags/{}", name))?.peel_to_commit()?;
+            let version: (u32, u32, u32) = (c[1].parse()?, c[2].parse()?, c[3].parse()?);
+            let entry = tags.entry(commit.id()).or_insert(version);
+            if version > *entry {
+                *entry = version;
+            }
+        }
+    }
+
+    let head = repo.head()?.peel_to_commit()?.id();
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL);
+    walk.push(head)?;
+    let mut found = None;
+    for oid in walk {
+        let oid = oid?;
+        if let Some(version) = tags.get(&oid) {
+            found = Some((oid, *version));
+            break;
+        }
+    }
+    let (tag, (major, minor, patch)) = found
+        .ok_or_else(|| HEMTTError::generic("No version tag was found", "Tag a commit with a version such as `v1.0.0`"))?;
+
+    let mut walk = repo.revwalk()?;
+    walk.push(head)?;
+    walk.hide(tag)?;
+    let build = walk.count();
+
+    let dirty = !repo.statuses(Some(StatusOptions::new().include_untracked(false)))?.is_empty();
+    let version = format!("{}.{}.{}.{}{}", major, minor, patch, build, if dirty { "-dirty" } else { "" });
+    debug!("Version from git: {}", version);
+    Ok(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::{Commit, Signature};
+
+    use super::*;
+    use crate::test::TempDir;
+
+    /// Commits `content` as `file.txt`, returning the new commit
+    fn commit(repo: &Repository, root: &Path, content: &str) -> Oid {
+        std::fs::write(root.join("file.txt"), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("HEMTT", "hemtt@example.com").unwrap();
+        let parents: Vec<Commit> = repo.head().and_then(|head| head.peel_to_commit()).into_iter().collect();
+        let parents: Vec<&Commit> = parents.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, content, &tree, &parents)
+            .unwrap()
+    }
+
+    fn repository(name: &str) -> (TempDir, Repository) {
+        let root = TempDir::new(&format!("git-{}", name));
+        let repo = Repository::init(&*root).unwrap();
+        (root, repo)
+    }
+
+    #[test]
+    fn tagged() {
+        let (root, repo) = repository("tagged");
+        let tagged = commit(&repo, &root, "first");
+        repo.tag_lightweight("v1.2.0", &repo.find_object(tagged, None).unwrap(), false)
+            .unwrap();
+        assert_eq!("1.2.0.0", version(&root).unwrap());
+
+        commit(&repo, &root, "second");
+        commit(&repo, &root, "third");
+        assert_eq!("1.2.0.2", version(&root).unwrap());
+
+        std::fs::write(root.join("file.txt"), "changed").unwrap();
+        assert_eq!("1.2.0.2-dirty", version(&root).unwrap());
+    }
+
+    #[test]
+    fn untagged() {
+        let (root, repo) = repository("untagged");
+        commit(&repo, &root, "first");
+        repo.tag_lightweight("release", &repo.revparse_single("HEAD").unwrap(), false)
+            .unwrap();
+        assert!(version(&root).is_err());
+    }
+}
@@ -7,11 +7,14 @@ use handlebars::to_json;
 use serde::{Deserialize, Serialize};
 use serde_json::value::Value as Json;
 
-use crate::error::PrintableError;
 use crate::HEMTTError;
 
+mod git;
+mod modcpp;
 mod signing;
 
+pub use modcpp::ModCpp;
+
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Project {
     pub name: String,
@@ -26,6 +29,10 @@ pub struct Project {
     #[serde(default = "String::new")]
     pub version: String,
 
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub version_source: String,
+
     #[serde(skip_serializing_if = "String::is_empty")]
     #[serde(default = "String::new")]
     pub modname: String,
@@ -69,6 +76,25 @@ pub struct Project {
     #[serde(rename(deserialize = "sigversion"))] // DEPRECATED
     pub sig_version: u8,
 
+    #[serde(default = "default_key_size")]
+    pub key_size: u32,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    private_key: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub private_key_env: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub modcpp: Option<ModCpp>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub changelog: Option<crate::commands::changelog::Group>,
+
     // Scripts
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(default = "Vec::new")]
@@ -89,6 +115,9 @@ pub struct Project {
     #[serde(skip_serializing_if = "HashMap::is_empty")]
     #[serde(default = "HashMap::new")]
     pub scripts: HashMap<String, crate::BuildScript>,
+
+    #[serde(skip)]
+    git_version: git::GitVersion,
 }
 impl Project {
     pub fn new(name: String, prefix: String, author: String, template: String) -> Self {
@@ -99,6 +128,7 @@ impl Project {
             template,
 
             version: String::new(),
+            version_source: String::new(),
 
             modname: String::new(),
             mainprefix: default_mainprefix(),
@@ -117,12 +147,20 @@ impl Project {
             key_name: String::new(),
             sig_name: String::new(),
             sig_version: default_sig_version(),
+            key_size: default_key_size(),
+            private_key: String::new(),
+            private_key_env: String::new(),
+
+            modcpp: None,
+            changelog: None,
 
             check: Vec::new(),
             postbuild: Vec::new(),
             prebuild: Vec::new(),
             releasebuild: Vec::new(),
             scripts: HashMap::new(),
+
+            git_version: git::GitVersion::default(),
         }
     }
 
@@ -152,20 +190,20 @@ impl Project {
     }
 
     /// Values used for rendering
-    pub fn get_variables(&self) -> BTreeMap<&'static str, Json> {
+    pub fn get_variables(&self) -> Result<BTreeMap<&'static str, Json>, HEMTTError> {
         let mut vars = BTreeMap::new();
         vars.insert("author", to_json(self.author.clone()));
         vars.insert("env", to_json(environment()));
         vars.insert("mainprefix", to_json(self.mainprefix.clone()));
         vars.insert("name", to_json(self.name.clone()));
         vars.insert("prefix", to_json(self.prefix.clone()));
-        vars.insert("version", to_json(self.version().unwrap_or_print()));
-        vars
+        vars.insert("version", to_json(self.version()?));
+        Ok(vars)
     }
 
     /// Render a handlebars string
     pub fn render(&self, text: &str, filename: Option<&str>) -> Result<String, HEMTTError> {
-        crate::render::run(text, filename, &self.get_variables())
+        crate::render::run(text, filename, &self.get_variables()?)
     }
 
     /// `@modname` without `@`, uses prefix if undefined by project file
@@ -180,13 +218,24 @@ impl Project {
     /// Version number as defined or detected by the templating engine
     pub fn version(&self) -> Result<String, HEMTTError> {
         if self.version.is_empty() {
-            let template = crate::commands::Template::new();
-            template.get_version()
+            match self.version_source.as_str() {
+                "git" => self.git_version.get(),
+                "" => crate::commands::Template::new().get_version(),
+                source => Err(HEMTTError::generic(
+                    format!("Invalid version source `{}`", source),
+                    "Use `git` or remove `version_source`",
+                )),
+            }
         } else {
             Ok(self.version.clone().trim().to_string())
         }
     }
 
+    /// Forgets the version read from git, so the next `version` picks up new commits and tags
+    pub fn refresh_version(&self) {
+        self.git_version.clear();
+    }
+
     /// Release directory `releases/{version}/@{modname}`
     pub fn release_dir(&self) -> Result<PathBuf, HEMTTError> {
         let version = self.version()?;
@@ -245,3 +294,7 @@ fn default_reuse_private_key() -> Option<bool> {
 pub fn default_sig_version() -> u8 {
     3
 }
+
+fn default_key_size() -> u32 {
+    1024
+}
@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use super::Project;
+
+use crate::HEMTTError;
+
+/// `[modcpp]` section used to generate `mod.cpp` and `meta.cpp`
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ModCpp {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub name: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub picture: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub logo: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub logo_over: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub action: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub tooltip: String,
+
+    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default = "String::new")]
+    pub overview: String,
+
+    /// Steam Workshop id, `meta.cpp` is only generated when set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub workshop_id: Option<u64>,
+}
+
+impl ModCpp {
+    /// Images referenced by `mod.cpp`, relative to the release folder
+    pub fn images(&self, p: &Project) -> Result<Vec<String>, HEMTTError> {
+        let mut images = Vec::new();
+        for (key, value) in &[
+            ("picture", &self.picture),
+            ("logo", &self.logo),
+            ("logo_over", &self.logo_over),
+        ] {
+            if !value.is_empty() {
+                images.push(p.render(value, Some(&format!("project:modcpp:{}", key)))?);
+            }
+        }
+        Ok(images)
+    }
+
+    /// Name shown in the launcher, the project name unless set
+    fn name<'a>(&'a self, p: &'a Project) -> &'a String {
+        if self.name.is_empty() {
+            &p.name
+        } else {
+            &self.name
+        }
+    }
+
+    /// Contents of `mod.cpp`
+    pub fn mod_cpp(&self, p: &Project) -> Result<String, HEMTTError> {
+        let mut out = String::new();
+        for (key, value) in &[
+            ("name", self.name(p)),
+            ("picture", &self.picture),
+            ("logo", &self.logo),
+            ("logoOver", &self.logo_over),
+            ("action", &self.action),
+            ("tooltip", &self.tooltip),
+            ("overview", &self.overview),
+        ] {
+            if !value.is_empty() {
+                let value = p.render(value, Some(&format!("project:modcpp:{}", key)))?;
+                out.push_str(&format!("{} = \"{}\";\n", key, escape(&value)));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Contents of `meta.cpp`, if a workshop id is set
+    pub fn meta_cpp(&self, p: &Project) -> Result<Option<String>, HEMTTError> {
+        let id = match self.workshop_id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let name = p.render(self.name(p), Some("project:modcpp:name"))?;
+        Ok(Some(format!(
+            "protocol = 1;\npublishedid = {};\nname = \"{}\";\n",
+            id,
+            escape(&name)
+        )))
+    }
+}
+
+/// Quotes are doubled inside config strings
+fn escape(value: &str) -> String {
+    value.replace("\"", "\"\"")
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use super::Project;
 
 use crate::HEMTTError;
@@ -23,6 +25,31 @@ impl Project {
         })
     }
 
+    /// Size in bits of generated private keys
+    pub fn get_key_size(&self) -> Result<u32, HEMTTError> {
+        match self.key_size {
+            1024 | 2048 | 4096 => Ok(self.key_size),
+            _ => Err(HEMTTError::generic(
+                format!("Invalid key size `{}`", self.key_size),
+                "Use 1024, 2048 or 4096",
+            )),
+        }
+    }
+
+    /// Is the private key kept outside of the project
+    pub fn external_private_key(&self) -> bool {
+        !self.private_key.is_empty()
+    }
+
+    /// Location of the .biprivatekey, `keys/{key_name}.biprivatekey` unless set by the project
+    pub fn get_private_key_path(&self) -> Result<PathBuf, HEMTTError> {
+        Ok(if self.external_private_key() {
+            PathBuf::from(self.render(&self.private_key, Some("project:private_key"))?)
+        } else {
+            PathBuf::from(format!("keys/{}.biprivatekey", self.get_key_name()?))
+        })
+    }
+
     /// Get the name for .bisign files
     pub fn get_sig_name(&self, pbo: &str) -> Result<String, HEMTTError> {
         Ok(if self.sig_name.is_empty() {
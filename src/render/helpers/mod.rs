@@ -1,8 +1,6 @@
 use chrono::prelude::*;
 use git2::Repository;
-use handlebars::{Context, Handlebars, Helper, HelperResult, JsonRender, Output, RenderContext};
-
-use crate::error::*;
+use handlebars::{Context, Handlebars, Helper, HelperResult, JsonRender, Output, RenderContext, RenderError};
 
 pub fn date(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
     let param = if let Some(p) = h.param(0) {
@@ -10,7 +8,7 @@ pub fn date(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out:
     } else {
         "%s".to_string()
     };
-    out.write(&Local::now().format(param.as_ref()).to_string()).unwrap_or_print();
+    out.write(&Local::now().format(param.as_ref()).to_string())?;
     Ok(())
 }
 
@@ -22,11 +20,13 @@ pub fn git(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out:
     };
     let params: Vec<&str> = param.split_whitespace().collect();
 
-    let repo = Repository::open(".").unwrap_or_print();
+    let repo = Repository::open(".").map_err(|e| RenderError::new(format!("Unable to open the git repository: {}", e)))?;
 
     if params[0] == "id" {
         // SHA-1 Commit Hash
-        let rev = repo.revparse_single("HEAD").unwrap_or_print();
+        let rev = repo
+            .revparse_single("HEAD")
+            .map_err(|e| RenderError::new(format!("Unable to find the git commit: {}", e)))?;
         let id = rev.id().to_string();
 
         // Default to has length of 8 characters
@@ -35,8 +35,8 @@ pub fn git(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out:
             None => 8,
         };
 
-        let id_sliced = &id[0..length];
-        out.write(id_sliced).unwrap_or_print();
+        let id_sliced = &id[0..length.min(id.len())];
+        out.write(id_sliced)?;
     }
     Ok(())
 }
@@ -41,6 +41,6 @@ pub fn run(source: &str, filename: Option<&str>, data: &BTreeMap<&'static str, J
                 HEMTTError::GENERIC("Render error".to_string(), e.reason.to_string())
             }
         }
-        _ => unimplemented!(),
+        e => HEMTTError::GENERIC("Render error".to_string(), e.to_string()),
     })
 }
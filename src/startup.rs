@@ -1,18 +1,12 @@
 use std::io::Read;
 use std::path::Path;
 
-use crate::error::PrintableError;
 use crate::HEMTTError;
 
-macro_rules! exec {
-    ($c:expr) => {
-        $c().unwrap_or_print();
-    };
-}
-
-pub fn startup() {
-    exec!(check_git_ignore);
-    exec!(deprecated);
+pub fn startup() -> Result<(), HEMTTError> {
+    check_git_ignore()?;
+    deprecated()?;
+    Ok(())
 }
 
 /// Checks for the recommended items in a .gitignore
@@ -0,0 +1,31 @@
+//! Helpers shared by the tests
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A folder in the temporary folder that is removed when dropped, even when the test fails
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty folder, `name` keeps the folders of different tests apart
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("hemtt-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
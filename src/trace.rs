@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::HEMTTError;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref START: Instant = Instant::now();
+    static ref EVENTS: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+}
+
+/// A complete event in the Chrome trace event format
+#[derive(Serialize)]
+struct Event {
+    name: String,
+    cat: &'static str,
+    ph: &'static str,
+    /// Microseconds since tracing started
+    ts: u64,
+    dur: u64,
+    pid: u32,
+    tid: usize,
+    args: BTreeMap<&'static str, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: &'a [Event],
+    display_time_unit: &'static str,
+}
+
+/// Start recording spans
+pub fn enable() {
+    lazy_static::initialize(&START);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records the time until it is dropped
+pub struct Span {
+    name: String,
+    cat: &'static str,
+    start: Option<Instant>,
+    args: BTreeMap<&'static str, String>,
+}
+
+impl Span {
+    pub fn arg<S: Into<String>>(mut self, key: &'static str, value: S) -> Self {
+        if self.start.is_some() {
+            self.args.insert(key, value.into());
+        }
+        self
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let event = Event {
+                name: std::mem::replace(&mut self.name, String::new()),
+                cat: self.cat,
+                ph: "X",
+                ts: micros(start.duration_since(*START)),
+                dur: micros(start.elapsed()),
+                pid: std::process::id(),
+                // 0 is the main thread, rayon workers start at 1
+                tid: rayon::current_thread_index().map(|i| i + 1).unwrap_or(0),
+                args: std::mem::replace(&mut self.args, BTreeMap::new()),
+            };
+            EVENTS.lock().unwrap().push(event);
+        }
+    }
+}
+
+/// Starts a span, which does nothing unless tracing is enabled
+pub fn span<S: Into<String>>(name: S, cat: &'static str) -> Span {
+    if is_enabled() {
+        Span {
+            name: name.into(),
+            cat,
+            start: Some(Instant::now()),
+            args: BTreeMap::new(),
+        }
+    } else {
+        Span {
+            name: String::new(),
+            cat,
+            start: None,
+            args: BTreeMap::new(),
+        }
+    }
+}
+
+/// Writes every recorded span as Chrome trace event JSON
+pub fn write<P: AsRef<Path>>(path: P) -> Result<(), HEMTTError> {
+    let path = path.as_ref();
+    let events = EVENTS.lock().unwrap();
+    let out = create_file!(path)?;
+    serde_json::to_writer(
+        out,
+        &Trace {
+            trace_events: &events,
+            display_time_unit: "ms",
+        },
+    )?;
+    Ok(())
+}
+
+fn micros(time: std::time::Duration) -> u64 {
+    time.as_secs() * 1_000_000 + u64::from(time.subsec_micros())
+}
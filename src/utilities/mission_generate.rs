@@ -36,8 +36,7 @@ impl Command for MissionGenerate {
             let cap = m_re.captures(&mission_source).unwrap();
             cap.get(2).unwrap().as_str()
         } else {
-            error!("Mission folder must have a map defined");
-            std::process::exit(1);
+            return Err(HEMTTError::simple("Mission folder must have a map defined"));
         };
 
         let maps: Vec<_> = args.values_of("maps").unwrap().collect();